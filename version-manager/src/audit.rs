@@ -0,0 +1,144 @@
+use crate::{
+    VersionResult,
+    files::{ModifyTrackedFiles, Suggestion, TrackedFiles, VersionFile, normalize, relative_path},
+    prompt,
+};
+use ignore::WalkBuilder;
+use std::path::{Path, PathBuf};
+
+/// A literal occurrence of a version in a file that isn't tracked
+#[derive(Clone, Debug, PartialEq)]
+pub struct Occurrence {
+    /// The path of the file, relative to the project root
+    pub file: String,
+    /// `root` or the name of the package whose version was found
+    pub scope: String,
+    /// The path as it would be tracked, relative to the scope's directory
+    pub tracked_as: String,
+    pub suggestion: Suggestion,
+}
+
+/// Walk the project, respecting ignore files, and find versions in files that aren't tracked
+///
+/// When `only` is given, just that package's version is searched for. A package's version is
+/// only looked for under its own directory, as that's where its files are tracked from.
+pub fn find_untracked(ver: &VersionFile, only: Option<&str>) -> VersionResult<Vec<Occurrence>> {
+    let mut versions = vec![];
+    let mut tracked: Vec<PathBuf> = vec![];
+    if only.is_none() {
+        versions.push(("root".to_string(), ver.version.to_string(), &ver.root));
+    }
+    for file in ver.files.iter() {
+        if let Ok(path) = file.path(&ver.root) {
+            tracked.push(path);
+        }
+    }
+    for (name, pkg) in ver.package.iter() {
+        if only.is_none_or(|only| only == name) {
+            versions.push((name.clone(), pkg.version.to_string(), &pkg.root));
+        }
+        for file in pkg.files.iter() {
+            if let Ok(path) = file.path(&pkg.root) {
+                tracked.push(path);
+            }
+        }
+    }
+    tracked.push(ver.root.join("VERSION.toml"));
+
+    let mut paths = vec![];
+    for entry in WalkBuilder::new(&ver.root).require_git(false).build() {
+        let entry = entry?;
+        if entry.file_type().is_some_and(|ft| ft.is_file()) {
+            let path = normalize(entry.path());
+            if !tracked.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+
+    let mut occurrences = vec![];
+    for path in paths.iter() {
+        let file = relative(path, &ver.root);
+        let candidate = TrackedFiles::new(file.clone(), String::new());
+        for (scope, version, dir) in versions.iter() {
+            if !path.starts_with(dir) {
+                continue;
+            }
+            // Binary and unreadable files can't hold a version we could track
+            let suggestions = match candidate.suggest(version, &ver.root) {
+                Ok(suggestions) => suggestions,
+                Err(_) => continue,
+            };
+            for suggestion in suggestions {
+                occurrences.push(Occurrence {
+                    file: file.clone(),
+                    scope: scope.clone(),
+                    tracked_as: relative(path, dir),
+                    suggestion,
+                });
+            }
+        }
+    }
+    occurrences.sort_by(|a, b| {
+        (&a.file, a.suggestion.found.line).cmp(&(&b.file, b.suggestion.found.line))
+    });
+    Ok(occurrences)
+}
+
+/// Report untracked versions and offer to start tracking each one
+///
+/// Without a terminal to ask on, the command that would track each one is printed instead.
+/// Returns whether any new files were tracked.
+pub fn audit(ver: &mut VersionFile, only: Option<&str>) -> VersionResult<bool> {
+    let occurrences = find_untracked(ver, only)?;
+    let cwd = std::env::current_dir()?;
+    let mut changed = false;
+    for occurrence in occurrences {
+        let found = &occurrence.suggestion.found;
+        println!(
+            "[{}] {}:{}: {}",
+            occurrence.scope,
+            occurrence.file,
+            found.line,
+            found.text.trim()
+        );
+        if !prompt::interactive() {
+            let cmd = match occurrence.scope.as_str() {
+                "root" => "version file track".to_string(),
+                name => format!("version package {} file track", name),
+            };
+            // The command resolves the path from wherever it's run
+            let path = relative_path(&std::path::absolute(ver.root.join(&occurrence.file))?, &cwd);
+            println!(
+                "  track with `{} {} --line {}`",
+                cmd,
+                path.display(),
+                found.line
+            );
+            continue;
+        }
+        if !prompt::confirm(&format!("Track {}:{}?", occurrence.file, found.line))? {
+            continue;
+        }
+        // Checked like `file track`, but a refusal only skips this occurrence so the answers
+        // already given aren't lost
+        let file = TrackedFiles::new(occurrence.tracked_as, occurrence.suggestion.expr);
+        let tracked = match occurrence.scope.as_str() {
+            "root" => ver.track_file(file, false),
+            name => ver.get_package_mut(name)?.track_file(file, false),
+        };
+        match tracked {
+            Ok(_) => changed = true,
+            Err(e) => println!("warning: {}", e),
+        }
+    }
+    Ok(changed)
+}
+
+fn relative(path: &Path, root: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(rel) => rel.to_string_lossy().to_string(),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
@@ -1,6 +1,6 @@
 use clap::{CommandFactory, Parser};
 use std::env::current_dir;
-use version_manager::{VersionError, cli, run};
+use version_manager::{VersionError, cli, files::VersionFile, run};
 
 fn main() {
     let mut args = cli::Cli::parse();
@@ -11,16 +11,15 @@ fn main() {
             VersionError::IoError(e).terminate(&mut cli::Cli::command());
         }
     };
-    let version_file = curr_dir.join("VERSION.toml");
+    let version_file = VersionFile::discover(&curr_dir);
 
     match args.run() {
-        Ok(scope) => match scope {
-            Some(scope) => match run::run(scope, version_file) {
-                Ok(_) => {}
-                Err(e) => e.terminate(&mut cli::Cli::command()),
-            },
-            None => (),
-        },
+        Ok(Some(scope)) => {
+            if let Err(e) = run::run(scope, version_file) {
+                e.terminate(&mut cli::Cli::command());
+            }
+        }
+        Ok(None) => (),
         Err(e) => e.terminate(&mut cli::Cli::command()),
     }
 }
@@ -0,0 +1,795 @@
+use crate::{
+    VersionError, VersionResult,
+    files::VersionFile,
+    git::{self, Commit},
+};
+use clap::ValueEnum;
+use regex::Regex;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The `[changelog]` table in VERSION.toml
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct ChangelogConfig {
+    /// The repository URL compare links are built from, like `https://github.com/owner/repo`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub repository: Option<String>,
+    /// A template for commit links, with `{sha}` replaced by the full commit hash. Defaults to
+    /// `{repository}/commit/{sha}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub commit_url: Option<String>,
+}
+
+impl ChangelogConfig {
+    pub fn is_empty(&self) -> bool {
+        self == &ChangelogConfig::default()
+    }
+
+    /// The URL for a commit, if the repository or a template is configured
+    pub fn commit_link(&self, sha: &str) -> Option<String> {
+        match (&self.commit_url, &self.repository) {
+            (Some(template), _) => Some(template.replace("{sha}", sha)),
+            (None, Some(repository)) => Some(format!(
+                "{}/commit/{}",
+                repository.trim_end_matches('/'),
+                sha
+            )),
+            (None, None) => None,
+        }
+    }
+}
+
+/// The Keep a Changelog entry categories, in the order they appear in a release
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ValueEnum)]
+pub enum EntryType {
+    Added,
+    Changed,
+    Deprecated,
+    Removed,
+    Fixed,
+    Security,
+}
+
+impl fmt::Display for EntryType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EntryType::Added => write!(f, "Added"),
+            EntryType::Changed => write!(f, "Changed"),
+            EntryType::Deprecated => write!(f, "Deprecated"),
+            EntryType::Removed => write!(f, "Removed"),
+            EntryType::Fixed => write!(f, "Fixed"),
+            EntryType::Security => write!(f, "Security"),
+        }
+    }
+}
+
+/// Where the changelog for the root or a package lives, and the label its releases get
+///
+/// A package's `changelog` is relative to its directory. Without one, packages with a `path`
+/// keep a CHANGELOG.md there, and the rest share the root changelog with their releases
+/// labelled by tag, like `cli@1.2.0`. Other releases are labelled by version, with a `v` in
+/// front when the changelog's existing releases have one.
+pub fn location(ver: &VersionFile, package: Option<&str>) -> VersionResult<(PathBuf, String)> {
+    let version = match package {
+        Some(name) => &ver.get_package(name)?.version,
+        None => &ver.version,
+    };
+    location_of(ver, package, version)
+}
+
+/// Like [`location`], but labelling the release of `version` rather than the current version
+pub fn location_of(
+    ver: &VersionFile,
+    package: Option<&str>,
+    version: &Version,
+) -> VersionResult<(PathBuf, String)> {
+    let path = match package {
+        None => ver.root.join("CHANGELOG.md"),
+        Some(name) => {
+            let pkg = ver.get_package(name)?;
+            match (&pkg.changelog, &pkg.path) {
+                (Some(changelog), _) => pkg.root.join(changelog),
+                (None, Some(_)) => pkg.root.join("CHANGELOG.md"),
+                (None, None) => {
+                    let label = git::tag_name(ver, Some(name), version);
+                    return Ok((ver.root.join("CHANGELOG.md"), label));
+                }
+            }
+        }
+    };
+    let label = match uses_v_prefix(&path)? {
+        true => format!("v{}", version),
+        false => version.to_string(),
+    };
+    Ok((path, label))
+}
+
+/// Whether the newest release in the changelog at `path` is headed like `## [v1.2.0]`, so new
+/// releases can follow the same style
+fn uses_v_prefix(path: &Path) -> VersionResult<bool> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(text
+        .lines()
+        .find(|line| line.starts_with("## ") && !is_unreleased(line))
+        .and_then(|line| line[3..].trim_start_matches('[').strip_prefix('v'))
+        .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit())))
+}
+
+/// Seconds since the Unix epoch, taken from `SOURCE_DATE_EPOCH` when it's set so releases
+/// can be reproduced
+pub fn now() -> u64 {
+    if let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH")
+        && let Ok(secs) = epoch.trim().parse()
+    {
+        return secs;
+    }
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Format seconds since the Unix epoch as a `YYYY-MM-DD` date in UTC
+pub fn date(secs: u64) -> String {
+    // Howard Hinnant's days-to-civil algorithm
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+pub fn today() -> String {
+    date(now())
+}
+
+/// Entries grouped under a `### Category` heading
+pub type Sections = Vec<(String, Vec<String>)>;
+
+/// Render a `## heading - date` release section
+pub fn render_release(heading: &str, date: &str, sections: &Sections) -> String {
+    let mut text = format!("## {} - {}\n", heading, date);
+    for (category, entries) in sections.iter() {
+        if entries.is_empty() {
+            continue;
+        }
+        text.push_str(&format!("\n### {}\n\n", category));
+        for entry in entries.iter() {
+            text.push_str(&format!("- {}\n", entry));
+        }
+    }
+    text
+}
+
+/// Add a release section to the changelog at `path`, above the newest release and below any
+/// Unreleased section, creating the file if it doesn't exist
+pub fn insert_release(path: &Path, heading: &str, sections: &Sections) -> VersionResult<()> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::from("# Changelog\n"),
+        Err(e) => return Err(e.into()),
+    };
+    let release = render_release(heading, &today(), sections);
+    let mut lines: Vec<&str> = text.lines().collect();
+    let at = lines
+        .iter()
+        .position(|line| line.starts_with("## ") && !is_unreleased(line));
+    let release_lines: Vec<&str> = release.lines().collect();
+    match at {
+        Some(at) => {
+            let mut insert = release_lines;
+            insert.push("");
+            lines.splice(at..at, insert);
+        }
+        None => {
+            if lines.last().is_some_and(|line| !line.is_empty()) {
+                lines.push("");
+            }
+            lines.extend(release_lines);
+        }
+    }
+    let mut text = lines.join("\n");
+    text.push('\n');
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, text)?;
+    Ok(())
+}
+
+/// Whether a `## ` heading is the Unreleased section
+pub fn is_unreleased(line: &str) -> bool {
+    line.trim_start_matches('#')
+        .trim()
+        .trim_start_matches('[')
+        .to_ascii_lowercase()
+        .starts_with("unreleased")
+}
+
+fn read_lines(path: &Path) -> VersionResult<Vec<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(text.lines().map(|l| l.to_string()).collect()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(vec![
+            "# Changelog".to_string(),
+            String::new(),
+            "## [Unreleased]".to_string(),
+        ]),
+        Err(e) => Err(e.into()),
+    }
+}
+
+fn write_lines(path: &Path, lines: &[String]) -> VersionResult<()> {
+    let mut text = lines.join("\n").trim_end().to_string();
+    text.push('\n');
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, text)?;
+    Ok(())
+}
+
+fn is_link_definition(line: &str) -> bool {
+    line.starts_with('[') && line.contains("]: ")
+}
+
+/// The index the section starting at `start` ends at, stopping at the next release or the
+/// link definitions at the bottom of the file
+fn section_end(lines: &[String], start: usize) -> usize {
+    let mut end = lines.len();
+    for (idx, line) in lines.iter().enumerate().skip(start + 1) {
+        if line.starts_with("## ") {
+            return idx;
+        }
+        if is_link_definition(line) && end == lines.len() {
+            end = idx;
+        } else if !line.trim().is_empty() && !is_link_definition(line) {
+            end = lines.len();
+        }
+    }
+    end
+}
+
+/// Find the Unreleased section, adding one above the newest release when there isn't one
+fn unreleased(lines: &mut Vec<String>) -> usize {
+    if let Some(idx) = lines
+        .iter()
+        .position(|l| l.starts_with("## ") && is_unreleased(l))
+    {
+        return idx;
+    }
+    let at = lines
+        .iter()
+        .position(|l| l.starts_with("## ") || is_link_definition(l))
+        .unwrap_or(lines.len());
+    lines.splice(at..at, ["## [Unreleased]".to_string(), String::new()]);
+    at
+}
+
+/// Where a `### Category` heading sorts, the Keep a Changelog types first in their usual
+/// order and anything else after them
+fn category_rank(category: &str) -> usize {
+    EntryType::from_str(category, true)
+        .ok()
+        .and_then(|kind| EntryType::value_variants().iter().position(|v| *v == kind))
+        .unwrap_or(EntryType::value_variants().len())
+}
+
+/// Insert `entry` under `### category` in the section starting at `start`, adding the heading
+/// when the section doesn't have it yet
+fn insert_entry(lines: &mut Vec<String>, start: usize, category: &str, entry: String) {
+    let end = section_end(lines, start);
+    let heading = format!("### {}", category);
+    match lines[start..end].iter().position(|l| *l == heading) {
+        Some(offset) => {
+            // After the last entry under the heading
+            let at = start + offset;
+            let sub_end = lines[at + 1..end]
+                .iter()
+                .position(|l| l.starts_with("### "))
+                .map_or(end, |idx| at + 1 + idx);
+            let last = lines[at + 1..sub_end]
+                .iter()
+                .rposition(|l| !l.trim().is_empty())
+                .map_or(at + 1, |idx| at + 1 + idx + 1);
+            if last == at + 1 {
+                lines.splice(last..last, [String::new(), entry]);
+            } else {
+                lines.insert(last, entry);
+            }
+        }
+        None => {
+            // Before the first heading for a later category, keeping the usual order
+            let rank = category_rank(category);
+            let at = lines[start + 1..end]
+                .iter()
+                .position(|l| {
+                    l.strip_prefix("### ")
+                        .is_some_and(|other| category_rank(other) > rank)
+                })
+                .map(|idx| start + 1 + idx)
+                .unwrap_or_else(|| {
+                    let mut at = end;
+                    while at > start + 1 && lines[at - 1].trim().is_empty() {
+                        at -= 1;
+                    }
+                    at
+                });
+            let mut insert = vec![];
+            if !lines[at - 1].trim().is_empty() {
+                insert.push(String::new());
+            }
+            insert.extend([heading, String::new(), entry]);
+            if at < lines.len() && !lines[at].trim().is_empty() {
+                insert.push(String::new());
+            }
+            lines.splice(at..at, insert);
+        }
+    }
+}
+
+/// Add an entry to the Unreleased section under the heading for its type
+pub fn add_entry(path: &Path, kind: EntryType, message: &str) -> VersionResult<()> {
+    let mut lines = read_lines(path)?;
+    let start = unreleased(&mut lines);
+    insert_entry(
+        &mut lines,
+        start,
+        &kind.to_string(),
+        format!("- {}", message.trim()),
+    );
+    write_lines(path, &lines)
+}
+
+/// A Conventional Commit subject, capturing the type, scope, breaking marker and description
+pub const CONVENTIONAL_COMMIT: &str = r"^(\w+)(?:\(([^)]+)\))?(!)?: (.+)$";
+
+/// The heading for a conventional commit type, `None` for types that don't belong in a
+/// changelog
+fn commit_category(kind: &str) -> Option<&'static str> {
+    match kind {
+        "feat" => Some("Added"),
+        "fix" => Some("Fixed"),
+        "perf" | "refactor" => Some("Changed"),
+        "deprecate" => Some("Deprecated"),
+        "revert" | "remove" => Some("Removed"),
+        "security" => Some("Security"),
+        "docs" | "style" | "test" | "tests" | "build" | "ci" | "chore" => None,
+        _ => Some("Changed"),
+    }
+}
+
+/// Turn commits into changelog entries linking their short SHAs
+///
+/// Conventional Commits are grouped under the heading their type maps to, with `docs`, `ci`,
+/// `chore` and the like left out. Anything else, or every commit when `plain` is set, is
+/// listed under `Commits`.
+pub fn commit_sections(commits: &[Commit], config: &ChangelogConfig, plain: bool) -> Sections {
+    let conventional = Regex::new(CONVENTIONAL_COMMIT).unwrap();
+    let mut sections: Sections = vec![];
+    for commit in commits.iter() {
+        let link = match config.commit_link(&commit.sha) {
+            Some(url) => format!("[`{}`]({})", commit.short_sha(), url),
+            None => format!("`{}`", commit.short_sha()),
+        };
+        let (category, message) = match conventional.captures(&commit.subject) {
+            Some(caps) if !plain => {
+                let category = match commit_category(&caps[1].to_ascii_lowercase()) {
+                    Some(category) => category,
+                    None => continue,
+                };
+                let mut message = String::new();
+                if caps.get(3).is_some() {
+                    message.push_str("**Breaking:** ");
+                }
+                if let Some(scope) = caps.get(2) {
+                    message.push_str(&format!("**{}:** ", scope.as_str()));
+                }
+                message.push_str(&caps[4]);
+                (category, message)
+            }
+            _ => ("Commits", commit.subject.clone()),
+        };
+        let entry = format!("{} {}", message, link);
+        match sections.iter_mut().find(|(name, _)| name == category) {
+            Some((_, entries)) => entries.push(entry),
+            None => sections.push((category.to_string(), vec![entry])),
+        }
+    }
+    sections.sort_by_key(|(category, _)| category_rank(category));
+    sections
+}
+
+/// Merge entries into the `label` release, or the Unreleased section when `label` is `None`,
+/// returning how many weren't already there
+///
+/// A missing release section is added in version order, or above the newest release when the
+/// label isn't a version.
+pub fn merge_release(
+    path: &Path,
+    label: Option<&str>,
+    date: &str,
+    sections: &Sections,
+) -> VersionResult<usize> {
+    let mut lines = read_lines(path)?;
+    let start = match label {
+        None => unreleased(&mut lines),
+        Some(label) => release_start(&mut lines, label, date)?,
+    };
+    let mut added = 0;
+    for (category, entries) in sections.iter() {
+        for entry in entries.iter() {
+            let entry = format!("- {}", entry);
+            let end = section_end(&lines, start);
+            if lines[start..end].contains(&entry) {
+                continue;
+            }
+            insert_entry(&mut lines, start, category, entry);
+            added += 1;
+        }
+    }
+    write_lines(path, &lines)?;
+    Ok(added)
+}
+
+/// Find the heading for the `label` release, adding an empty section when there isn't one
+fn release_start(lines: &mut Vec<String>, label: &str, date: &str) -> VersionResult<usize> {
+    let bare = label.strip_prefix('v').unwrap_or(label);
+    let heading_re = Regex::new(&format!(r"^## \[?v?{}\]?(?:\(|\s|$)", regex::escape(bare)))?;
+    if let Some(idx) = lines.iter().position(|l| heading_re.is_match(l)) {
+        return Ok(idx);
+    }
+    let version_re = Regex::new(r"^## \[?v?([^\]\s(]+)")?;
+    let release_version = |line: &str| {
+        version_re
+            .captures(line)
+            .and_then(|caps| Version::parse(&caps[1]).ok())
+    };
+    let newest = |l: &String| l.starts_with("## ") && !is_unreleased(l);
+    let at = match Version::parse(bare) {
+        Ok(version) => lines
+            .iter()
+            .position(|l| newest(l) && release_version(l).is_none_or(|other| other < version)),
+        Err(_) => lines.iter().position(newest),
+    };
+    let at = at
+        .or_else(|| lines.iter().position(|l| is_link_definition(l)))
+        .unwrap_or(lines.len());
+    let mut insert = vec![];
+    if at > 0 && !lines[at - 1].trim().is_empty() {
+        insert.push(String::new());
+    }
+    insert.extend([format!("## [{}] - {}", label, date), String::new()]);
+    let start = at + insert.len() - 2;
+    lines.splice(at..at, insert);
+    Ok(start)
+}
+
+/// Turn the Unreleased section into the `label` release, start a fresh Unreleased section,
+/// and point the compare links at `tag`
+///
+/// Compare links are kept in whichever style the file already uses, inline in the headings or
+/// as link definitions at the bottom. `repository` is used when the file has none yet.
+pub fn release(path: &Path, label: &str, tag: &str, repository: Option<&str>) -> VersionResult<()> {
+    let mut lines = read_lines(path)?;
+    let heading_re = Regex::new(&format!(r"^## \[{}\]", regex::escape(label)))?;
+    if lines.iter().any(|l| heading_re.is_match(l)) {
+        return Err(VersionError::InvalidChangelog(format!(
+            "{} already has a section for {}",
+            path.display(),
+            label
+        )));
+    }
+    let start = match lines
+        .iter()
+        .position(|l| l.starts_with("## ") && is_unreleased(l))
+    {
+        Some(start) => start,
+        None => {
+            return Err(VersionError::InvalidChangelog(format!(
+                "{} has no Unreleased section",
+                path.display()
+            )));
+        }
+    };
+    let inline_re = Regex::new(r"^## \[Unreleased\]\((.+)/compare/(.+)\.\.\.HEAD\)")?;
+    let definition_re = Regex::new(r"(?i)^\[unreleased\]: (.+)/compare/(.+)\.\.\.HEAD\s*$")?;
+    let date = today();
+    let compare = |base: &str, prev: Option<&str>| match prev {
+        Some(prev) => format!("{}/compare/{}...{}", base, prev, tag),
+        None => format!("{}/releases/tag/{}", base, tag),
+    };
+
+    if let Some(caps) = inline_re.captures(&lines[start].clone()) {
+        let (base, prev) = (&caps[1], &caps[2]);
+        lines[start] = format!("## [{}]({}) - {}", label, compare(base, Some(prev)), date);
+        let unreleased = format!("## [Unreleased]({}/compare/{}...HEAD)", base, tag);
+        lines.splice(start..start, [unreleased, String::new()]);
+        return write_lines(path, &lines);
+    }
+
+    lines[start] = format!("## [{}] - {}", label, date);
+    lines.splice(start..start, ["## [Unreleased]".to_string(), String::new()]);
+    let definition = lines.iter().position(|l| definition_re.is_match(l));
+    let (base, prev) = match definition {
+        Some(idx) => {
+            let caps = definition_re.captures(&lines[idx]).unwrap();
+            (Some(caps[1].to_string()), Some(caps[2].to_string()))
+        }
+        None => (
+            repository.map(|r| r.trim_end_matches('/').to_string()),
+            None,
+        ),
+    };
+    if let Some(base) = base {
+        let links = [
+            format!("[unreleased]: {}/compare/{}...HEAD", base, tag),
+            format!("[{}]: {}", label, compare(&base, prev.as_deref())),
+        ];
+        match definition {
+            Some(idx) => {
+                lines.splice(idx..idx + 1, links);
+            }
+            None => {
+                while lines.last().is_some_and(|l| l.trim().is_empty()) {
+                    lines.pop();
+                }
+                lines.push(String::new());
+                lines.extend(links);
+            }
+        }
+    }
+    write_lines(path, &lines)
+}
+
+/// One release section read back out of a changelog
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReleaseNotes {
+    pub version: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub date: Option<String>,
+    /// The entries under each `### Category` heading, in the order they appear. Entries before
+    /// the first heading are listed under `Other`.
+    pub sections: Vec<ReleaseSection>,
+    /// The section as written, without its heading
+    #[serde(skip)]
+    pub markdown: String,
+}
+
+#[derive(Serialize, Debug, Clone, PartialEq)]
+pub struct ReleaseSection {
+    pub category: String,
+    pub entries: Vec<String>,
+}
+
+/// Find the release section for `label` in changelog text
+///
+/// Headings are matched with or without brackets, a `v` prefix, a compare link, or a date, so
+/// `1.2.0` finds any of `## [1.2.0] - 2024-04-09`, `## [v1.2.0](...)` and `## v1.2.0`.
+pub fn find_release(text: &str, label: &str) -> VersionResult<Option<ReleaseNotes>> {
+    let label = label.strip_prefix('v').unwrap_or(label);
+    let heading_re = Regex::new(&format!(
+        r"^## \[?v?{}\]?(?:\([^)]*\))?(?:\s+-\s+(\S+))?\s*$",
+        regex::escape(label)
+    ))?;
+    let lines: Vec<String> = text.lines().map(|l| l.to_string()).collect();
+    let (start, date) = match lines
+        .iter()
+        .enumerate()
+        .find_map(|(idx, l)| heading_re.captures(l).map(|caps| (idx, caps.get(1))))
+    {
+        Some((start, date)) => (start, date.map(|d| d.as_str().to_string())),
+        None => return Ok(None),
+    };
+    let end = section_end(&lines, start);
+    let body = &lines[start + 1..end];
+
+    let mut sections: Vec<ReleaseSection> = vec![];
+    for line in body.iter() {
+        if let Some(category) = line.strip_prefix("### ") {
+            sections.push(ReleaseSection {
+                category: category.trim().to_string(),
+                entries: vec![],
+            });
+            continue;
+        }
+        let entry = line.strip_prefix("- ").or_else(|| line.strip_prefix("* "));
+        let continued = line.starts_with(char::is_whitespace) && !line.trim().is_empty();
+        if entry.is_none() && !continued {
+            continue;
+        }
+        if sections.is_empty() {
+            sections.push(ReleaseSection {
+                category: "Other".to_string(),
+                entries: vec![],
+            });
+        }
+        let entries = &mut sections.last_mut().unwrap().entries;
+        match (entry, entries.last_mut()) {
+            (Some(entry), _) => entries.push(entry.trim().to_string()),
+            (None, Some(last)) => {
+                last.push(' ');
+                last.push_str(line.trim());
+            }
+            (None, None) => {}
+        }
+    }
+    sections.retain(|section| !section.entries.is_empty());
+
+    Ok(Some(ReleaseNotes {
+        version: label.to_string(),
+        date,
+        sections,
+        markdown: body.join("\n").trim().to_string(),
+    }))
+}
+
+/// Read the release section for `label` from the changelog at `path`
+pub fn show(path: &Path, label: &str) -> VersionResult<ReleaseNotes> {
+    let text = match fs::read_to_string(path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    find_release(&text, label)?.ok_or_else(|| {
+        VersionError::InvalidChangelog(format!("{} has no section for {}", path.display(), label))
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn formats_dates() {
+        assert_eq!(date(0), "1970-01-01");
+        assert_eq!(date(951_782_400), "2000-02-29");
+        assert_eq!(date(1_712_620_800), "2024-04-09");
+    }
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_section_ends() {
+        let text = lines("# Changelog\n\n## [Unreleased]\n\n- a\n\n[unreleased]: x\n");
+        assert_eq!(section_end(&text, 2), 6);
+        let text = lines("## [Unreleased]\n\n- a\n## [1.0.0]\n");
+        assert_eq!(section_end(&text, 0), 3);
+    }
+
+    #[test]
+    fn renders_sections() {
+        let sections = vec![
+            ("Added".to_string(), vec!["A thing".to_string()]),
+            ("Fixed".to_string(), vec![]),
+        ];
+        assert_eq!(
+            render_release("[1.2.0]", "2024-04-09", &sections),
+            "## [1.2.0] - 2024-04-09\n\n### Added\n\n- A thing\n"
+        );
+    }
+
+    #[test]
+    fn releases_inline_compare_links() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("CHANGELOG.md");
+        fs::write(
+            &path,
+            "# Changelog\n\n## [Unreleased](https://example.com/r/compare/v2.5.0...HEAD)\n\n- A thing\n",
+        )
+        .unwrap();
+        release(&path, "v2.6.0", "v2.6.0", None).unwrap();
+        let text = fs::read_to_string(&path).unwrap();
+        let expected = format!(
+            "# Changelog\n\n\
+             ## [Unreleased](https://example.com/r/compare/v2.6.0...HEAD)\n\n\
+             ## [v2.6.0](https://example.com/r/compare/v2.5.0...v2.6.0) - {}\n\n\
+             - A thing\n",
+            today()
+        );
+        assert_eq!(text, expected);
+    }
+
+    #[test]
+    fn finds_releases() {
+        let text = "# Changelog\n\n\
+                    ## [Unreleased]\n\n- Pending\n\n\
+                    ## [v1.1.0](https://example.com/r/compare/v1.0.0...v1.1.0) - 2024-04-03\n\n\
+                    ### Added\n\n- A thing\n- Another\n  that wraps\n\n### Fixed\n\n- A bug\n\n\
+                    ## v1.0.0 - 2024-04-01\n\n- First\n\n\
+                    [unreleased]: https://example.com\n";
+        let notes = find_release(text, "1.1.0").unwrap().unwrap();
+        assert_eq!(notes.date.as_deref(), Some("2024-04-03"));
+        assert_eq!(notes.sections.len(), 2);
+        assert_eq!(notes.sections[0].category, "Added");
+        assert_eq!(notes.sections[0].entries, ["A thing", "Another that wraps"]);
+        assert_eq!(notes.sections[1].entries, ["A bug"]);
+        assert!(notes.markdown.starts_with("### Added"));
+        assert!(notes.markdown.ends_with("- A bug"));
+
+        let notes = find_release(text, "v1.0.0").unwrap().unwrap();
+        assert_eq!(notes.sections[0].category, "Other");
+        assert_eq!(notes.markdown, "- First");
+
+        assert!(find_release(text, "1.0").unwrap().is_none());
+    }
+
+    #[test]
+    fn groups_commits() {
+        let commit = |sha: &str, subject: &str| Commit {
+            sha: sha.to_string(),
+            subject: subject.to_string(),
+        };
+        let commits = [
+            commit("1111111aaaa", "fix(cli): Handle empty files"),
+            commit("2222222bbbb", "docs: Fix a typo"),
+            commit("3333333cccc", "feat!: New config format"),
+            commit("4444444dddd", "Tidy up"),
+        ];
+        let config = ChangelogConfig {
+            commit_url: Some("https://example.com/c/{sha}".to_string()),
+            ..Default::default()
+        };
+        let sections = commit_sections(&commits, &config, false);
+        assert_eq!(
+            sections,
+            vec![
+                (
+                    "Added".to_string(),
+                    vec!["**Breaking:** New config format [`3333333`](https://example.com/c/3333333cccc)".to_string()]
+                ),
+                (
+                    "Fixed".to_string(),
+                    vec!["**cli:** Handle empty files [`1111111`](https://example.com/c/1111111aaaa)".to_string()]
+                ),
+                (
+                    "Commits".to_string(),
+                    vec!["Tidy up [`4444444`](https://example.com/c/4444444dddd)".to_string()]
+                ),
+            ]
+        );
+        let sections = commit_sections(&commits, &ChangelogConfig::default(), true);
+        assert_eq!(sections.len(), 1);
+        assert_eq!(sections[0].1[1], "docs: Fix a typo `2222222`");
+    }
+
+    #[test]
+    fn merges_releases_in_version_order() {
+        let dir = assert_fs::TempDir::new().unwrap();
+        let path = dir.path().join("CHANGELOG.md");
+        fs::write(
+            &path,
+            "# Changelog\n\n## [Unreleased]\n\n## [v1.2.0] - 2024-04-09\n\n## v1.0.0 - 2024-04-01\n",
+        )
+        .unwrap();
+        let sections = vec![("Fixed".to_string(), vec!["A bug".to_string()])];
+        assert_eq!(
+            merge_release(&path, Some("1.1.0"), "2024-04-05", &sections).unwrap(),
+            1
+        );
+        assert_eq!(
+            merge_release(&path, Some("1.1.0"), "2024-04-05", &sections).unwrap(),
+            0
+        );
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "# Changelog\n\n## [Unreleased]\n\n## [v1.2.0] - 2024-04-09\n\n\
+             ## [1.1.0] - 2024-04-05\n\n### Fixed\n\n- A bug\n\n## v1.0.0 - 2024-04-01\n"
+        );
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{
+    VersionError, VersionResult,
+    changelog::{self, Sections},
+    files::VersionFile,
+    graph::{self, Cause, Change, change_level},
+    version::{Bump, bump},
+};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+/// The directory, relative to the project root, that pending changesets are kept in
+pub const CHANGES_DIR: &str = ".changes";
+
+/// The name a changeset uses for the root version
+pub const ROOT: &str = "root";
+
+/// A pending release intent, stored as a markdown file with the bumps as frontmatter
+///
+/// ```markdown
+/// ---
+/// root: minor
+/// cli: patch
+/// ---
+///
+/// Add the `--all` package selector
+/// ```
+#[derive(Clone, Debug, PartialEq)]
+pub struct Changeset {
+    /// `root` or a package name, and how far it should be bumped
+    pub bumps: BTreeMap<String, Bump>,
+    pub summary: String,
+}
+
+impl Changeset {
+    pub fn parse(file: &str, text: &str) -> VersionResult<Self> {
+        let invalid =
+            |reason: &str| VersionError::InvalidChangeset(format!("{}: {}", file, reason));
+        let mut lines = text.lines();
+        if lines.next().map(str::trim) != Some("---") {
+            return Err(invalid("missing frontmatter"));
+        }
+        let mut bumps = BTreeMap::new();
+        let mut closed = false;
+        for line in lines.by_ref() {
+            let line = line.trim();
+            if line == "---" {
+                closed = true;
+                break;
+            }
+            if line.is_empty() {
+                continue;
+            }
+            let (name, level) = match line.split_once(':') {
+                Some((name, level)) => (name.trim(), level.trim()),
+                None => {
+                    return Err(invalid(&format!(
+                        "expected `name: level`, found '{}'",
+                        line
+                    )));
+                }
+            };
+            let level = match level {
+                "major" => Bump::Major,
+                "minor" => Bump::Minor,
+                "patch" => Bump::Patch,
+                _ => return Err(invalid(&format!("unknown bump level '{}'", level))),
+            };
+            bumps.insert(name.trim_matches('"').to_string(), level);
+        }
+        if !closed {
+            return Err(invalid("unterminated frontmatter"));
+        }
+        if bumps.is_empty() {
+            return Err(invalid("no packages named"));
+        }
+        let summary = lines.collect::<Vec<_>>().join("\n").trim().to_string();
+        Ok(Changeset { bumps, summary })
+    }
+
+    pub fn render(&self) -> String {
+        let mut text = String::from("---\n");
+        for (name, level) in self.bumps.iter() {
+            text.push_str(&format!("{}: {}\n", name, level));
+        }
+        text.push_str(&format!("---\n\n{}\n", self.summary.trim()));
+        text
+    }
+}
+
+/// Write a new changeset into the changes directory, returning its path
+///
+/// Files are named after the current time and the start of the summary, so they sort in the
+/// order they were added.
+pub fn add(root: &Path, changeset: &Changeset) -> VersionResult<PathBuf> {
+    let dir = root.join(CHANGES_DIR);
+    fs::create_dir_all(&dir)?;
+    let slug: Vec<String> = changeset
+        .summary
+        .split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .take(5)
+        .map(|word| word.to_ascii_lowercase())
+        .collect();
+    let stem = format!("{}-{}", changelog::now(), slug.join("-"));
+    let mut path = dir.join(format!("{}.md", stem));
+    let mut n = 1;
+    while path.exists() {
+        n += 1;
+        path = dir.join(format!("{}-{}.md", stem, n));
+    }
+    fs::write(&path, changeset.render())?;
+    Ok(path)
+}
+
+/// Read every pending changeset, in the order they were added
+pub fn pending(root: &Path) -> VersionResult<Vec<(PathBuf, Changeset)>> {
+    let dir = root.join(CHANGES_DIR);
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut paths = vec![];
+    for entry in fs::read_dir(&dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|ext| ext == "md") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    let mut changesets = vec![];
+    for path in paths {
+        let text = fs::read_to_string(&path)?;
+        let changeset = Changeset::parse(&path.display().to_string(), &text)?;
+        changesets.push((path, changeset));
+    }
+    Ok(changesets)
+}
+
+/// The highest bump each package is owed across `changesets`
+pub fn merge(changesets: &[(PathBuf, Changeset)]) -> BTreeMap<String, Bump> {
+    let mut bumps: BTreeMap<String, Bump> = BTreeMap::new();
+    for (_, changeset) in changesets.iter() {
+        for (name, level) in changeset.bumps.iter() {
+            let entry = bumps.entry(name.clone()).or_insert(*level);
+            *entry = (*entry).max(*level);
+        }
+    }
+    bumps
+}
+
+/// The changelog sections and consumed changesets of a release, held back until the new
+/// versions are ready to be saved
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Release {
+    /// The changelog, release heading and sections to add for each package that changed
+    pub changelogs: Vec<(PathBuf, String, Sections)>,
+    pub consumed: Vec<PathBuf>,
+}
+
+impl Release {
+    pub fn is_empty(&self) -> bool {
+        self.consumed.is_empty()
+    }
+
+    /// Write the changelog sections and delete the consumed changesets
+    pub fn apply(self) -> VersionResult<()> {
+        for (path, heading, sections) in self.changelogs {
+            changelog::insert_release(&path, &heading, &sections)?;
+        }
+        for path in self.consumed {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+}
+
+/// Apply the highest pending bump to each package and work out the changelog entries for
+/// everything that changed
+///
+/// Nothing is written to disk, so a failed hook or save leaves the changelogs and changesets
+/// alone; [`Release::apply`] does that once the new versions are saved.
+pub fn release(ver: &mut VersionFile) -> VersionResult<Release> {
+    let changesets = pending(&ver.root)?;
+    if changesets.is_empty() {
+        println!("No pending changes");
+        return Ok(Release::default());
+    }
+    let bumps = merge(&changesets);
+    for name in bumps.keys() {
+        if name != ROOT {
+            ver.get_package(name)?;
+        }
+    }
+    let original = ver.package_versions();
+    let mut entries: BTreeMap<String, Vec<(Bump, String)>> = BTreeMap::new();
+    let record = |change: Change, entries: &mut BTreeMap<String, Vec<(Bump, String)>>| {
+        println!("{}", change.describe());
+        let level = change_level(&change.from, &change.to).unwrap_or(Bump::Patch);
+        let note = match change.cause {
+            Some(Cause::Dependency(dep)) => format!("Updated dependency {}", dep),
+            Some(Cause::Group(group)) => format!("Version synced with group {}", group),
+            Some(Cause::Inherit(source)) => format!("Version inherited from {}", source),
+            None => return,
+        };
+        entries
+            .entry(change.package)
+            .or_default()
+            .push((level, note));
+    };
+    for (_, changeset) in changesets.iter() {
+        for (name, level) in changeset.bumps.iter() {
+            entries
+                .entry(name.clone())
+                .or_default()
+                .push((*level, changeset.summary.clone()));
+        }
+    }
+
+    if let Some(level) = bumps.get(ROOT) {
+        let from = ver.version.clone();
+        bump(&mut ver.version, *level);
+        println!("{}: {} -> {}", ROOT, from, ver.version);
+        for change in graph::propagate_root(ver, &from)? {
+            record(change, &mut entries);
+        }
+    }
+    for name in graph::topo_order(&ver.package)? {
+        let level = match bumps.get(&name) {
+            Some(level) => *level,
+            None => continue,
+        };
+        let pkg = ver.get_package_mut(&name)?;
+        if let Some(ref inherit) = pkg.inherit {
+            return Err(VersionError::InheritedVersion(name, inherit.clone()));
+        }
+        // Bump from the version before the release, so a package that already moved with a
+        // dependency or its group isn't bumped twice
+        let mut target = original[&name].clone();
+        bump(&mut target, level);
+        if target <= pkg.version {
+            continue;
+        }
+        let from = std::mem::replace(&mut pkg.version, target);
+        println!("{}: {} -> {}", name, from, pkg.version);
+        for change in graph::propagate(ver, &name, &from)? {
+            record(change, &mut entries);
+        }
+    }
+
+    let mut release = Release::default();
+    for (name, entries) in entries {
+        let package = Some(name.as_str()).filter(|name| *name != ROOT);
+        let (path, label) = changelog::location(ver, package)?;
+        let sections: Sections = [Bump::Major, Bump::Minor, Bump::Patch]
+            .iter()
+            .map(|level| {
+                let heading = match level {
+                    Bump::Major => "Major Changes",
+                    Bump::Minor => "Minor Changes",
+                    Bump::Patch => "Patch Changes",
+                };
+                let notes = entries
+                    .iter()
+                    .filter(|(l, _)| l == level)
+                    .map(|(_, note)| note.clone())
+                    .collect();
+                (heading.to_string(), notes)
+            })
+            .collect();
+        release
+            .changelogs
+            .push((path, format!("[{}]", label), sections));
+    }
+    release.consumed = changesets.into_iter().map(|(path, _)| path).collect();
+    Ok(release)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn round_trips() {
+        let text = "---\nroot: minor\ncli: patch\n---\n\nAdd the `--all` selector\n";
+        let changeset = Changeset::parse("test.md", text).unwrap();
+        assert_eq!(changeset.bumps["root"], Bump::Minor);
+        assert_eq!(changeset.bumps["cli"], Bump::Patch);
+        assert_eq!(changeset.summary, "Add the `--all` selector");
+        assert_eq!(
+            changeset.render(),
+            "---\ncli: patch\nroot: minor\n---\n\nAdd the `--all` selector\n"
+        );
+    }
+
+    #[test]
+    fn rejects_bad_levels() {
+        assert!(matches!(
+            Changeset::parse("test.md", "---\ncli: huge\n---\n"),
+            Err(VersionError::InvalidChangeset(_))
+        ));
+        assert!(matches!(
+            Changeset::parse("test.md", "cli: patch\n"),
+            Err(VersionError::InvalidChangeset(_))
+        ));
+    }
+
+    #[test]
+    fn merges_to_the_highest_bump() {
+        let one = Changeset::parse("1.md", "---\ncli: patch\n---\none").unwrap();
+        let two = Changeset::parse("2.md", "---\ncli: minor\ncore: patch\n---\ntwo").unwrap();
+        let bumps = merge(&[(PathBuf::from("1.md"), one), (PathBuf::from("2.md"), two)]);
+        assert_eq!(bumps["cli"], Bump::Minor);
+        assert_eq!(bumps["core"], Bump::Patch);
+    }
+}
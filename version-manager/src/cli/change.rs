@@ -0,0 +1,59 @@
+use crate::{
+    VersionError,
+    changes::{Changeset, ROOT},
+    version::{Bump, Scope},
+};
+use clap::{Args, Parser, Subcommand, builder::NonEmptyStringValueParser};
+
+#[derive(Parser, Debug, Clone, PartialEq)]
+#[command(arg_required_else_help(true))]
+pub struct ChangeCommand {
+    #[clap(subcommand)]
+    pub command: Change,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+#[command(arg_required_else_help(true))]
+pub enum Change {
+    /// Record a pending change in the .changes directory
+    Add(AddChange),
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct AddChange {
+    /// How far to bump the affected packages on release
+    #[arg(value_enum)]
+    pub level: Bump,
+    /// The packages affected, or the root version when none are given
+    pub packages: Vec<String>,
+    /// A markdown summary of the change for the changelog
+    #[arg(short, long, value_parser = NonEmptyStringValueParser::new())]
+    pub message: String,
+}
+
+impl TryFrom<ChangeCommand> for Scope {
+    type Error = VersionError;
+
+    fn try_from(cmd: ChangeCommand) -> Result<Self, Self::Error> {
+        (&cmd).try_into()
+    }
+}
+
+impl TryFrom<&ChangeCommand> for Scope {
+    type Error = VersionError;
+
+    fn try_from(cmd: &ChangeCommand) -> Result<Self, Self::Error> {
+        match cmd.command {
+            Change::Add(ref add) => {
+                let names = match add.packages.is_empty() {
+                    true => vec![ROOT.to_string()],
+                    false => add.packages.clone(),
+                };
+                Ok(Scope::AddChange(Changeset {
+                    bumps: names.into_iter().map(|name| (name, add.level)).collect(),
+                    summary: add.message.clone(),
+                }))
+            }
+        }
+    }
+}
@@ -0,0 +1,93 @@
+use crate::{
+    VersionError,
+    changelog::EntryType,
+    version::{ChangelogOp, Scope},
+};
+use clap::{Args, Parser, Subcommand, builder::NonEmptyStringValueParser};
+use semver::Version;
+
+#[derive(Parser, Debug, Clone, PartialEq)]
+#[command(arg_required_else_help(true))]
+pub struct ChangelogCommand {
+    #[clap(subcommand)]
+    pub command: Changelog,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+#[command(arg_required_else_help(true))]
+pub enum Changelog {
+    /// Add an entry to the Unreleased section
+    Add(AddEntry),
+    /// Turn the Unreleased section into a release for the current version
+    Release,
+    /// Print the section for a release
+    Show(ShowRelease),
+    /// Add entries for the commits between two revisions
+    Generate(GenerateEntries),
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct AddEntry {
+    /// The kind of change
+    #[arg(long = "type", value_enum, default_value = "changed")]
+    pub kind: EntryType,
+    /// The entry text, in markdown
+    #[arg(value_parser = NonEmptyStringValueParser::new())]
+    pub message: String,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct ShowRelease {
+    /// The release to show, defaulting to the current version
+    #[arg(value_parser = NonEmptyStringValueParser::new())]
+    pub version: Option<String>,
+    /// Print the entries as JSON, grouped by category
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct GenerateEntries {
+    /// The revision to start after, defaulting to the release tag before `--to`
+    #[arg(long, value_parser = NonEmptyStringValueParser::new())]
+    pub from: Option<String>,
+    /// The revision to end at. A release tag fills in that release, anything else the
+    /// Unreleased section.
+    #[arg(long, default_value = "HEAD", value_parser = NonEmptyStringValueParser::new())]
+    pub to: String,
+    /// List every commit under `Commits` instead of grouping by Conventional Commit type
+    #[arg(long)]
+    pub plain: bool,
+}
+
+impl TryFrom<ChangelogCommand> for Scope {
+    type Error = VersionError;
+
+    fn try_from(cmd: ChangelogCommand) -> Result<Self, Self::Error> {
+        (&cmd).try_into()
+    }
+}
+
+impl TryFrom<&ChangelogCommand> for Scope {
+    type Error = VersionError;
+
+    fn try_from(cmd: &ChangelogCommand) -> Result<Self, Self::Error> {
+        let op = match cmd.command {
+            Changelog::Add(ref add) => ChangelogOp::Add(add.kind, add.message.clone()),
+            Changelog::Release => ChangelogOp::Release,
+            Changelog::Show(ref show) => {
+                let version = match show.version {
+                    Some(ref version) => Some(Version::parse(
+                        version.strip_prefix('v').unwrap_or(version),
+                    )?),
+                    None => None,
+                };
+                ChangelogOp::Show(version, show.json)
+            }
+            Changelog::Generate(ref generate) => {
+                ChangelogOp::Generate(generate.from.clone(), generate.to.clone(), generate.plain)
+            }
+        };
+        Ok(Scope::Changelog(op))
+    }
+}
@@ -1,5 +1,5 @@
 use super::VersionCommand;
-use crate::{VersionError, VersionResult, version::Scope};
+use crate::{VersionError, VersionResult, git::GitOptions, version::Scope};
 use clap::{
     Command, CommandFactory, Parser,
     builder::{Styles, styling::AnsiColor},
@@ -26,6 +26,15 @@ pub struct Cli {
     #[arg(long, value_parser = value_parser!(Shell), exclusive = true)]
     /// Generate shell completions
     pub generator: Option<Shell>,
+    /// Commit VERSION.toml and the tracked files after changing a version
+    #[arg(long, global = true)]
+    pub commit: bool,
+    /// With --commit, also create an annotated release tag for each changed version
+    #[arg(long, global = true, requires = "commit")]
+    pub tag: bool,
+    /// With --commit, commit even when the working tree has uncommitted changes
+    #[arg(long, global = true, requires = "commit")]
+    pub allow_dirty: bool,
 }
 
 impl Cli {
@@ -44,11 +53,22 @@ impl Cli {
             let mut cmd = Cli::command();
             cmd.set_bin_name("version");
             Self::print_completions(generator, &mut cmd)?;
-            return Ok(None);
+            Ok(None)
         } else if let Some(command) = self.command.take() {
-            return Ok(Some(command.try_into()?));
+            let scope: Scope = command.try_into()?;
+            if self.commit {
+                if !scope.changes_version() {
+                    return Err(VersionError::NothingToCommit);
+                }
+                let options = GitOptions {
+                    tag: self.tag,
+                    allow_dirty: self.allow_dirty,
+                };
+                return Ok(Some(Scope::Commit(options, Box::new(scope))));
+            }
+            Ok(Some(scope))
         } else {
-            return Err(VersionError::InvalidOperation);
+            Err(VersionError::InvalidOperation)
         }
     }
 }
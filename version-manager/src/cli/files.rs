@@ -6,6 +6,7 @@ use crate::{
 use clap::{Args, Parser, Subcommand, value_parser};
 use clio::ClioPath;
 use regex::Regex;
+use std::fmt;
 
 #[derive(Parser, Debug, Clone, PartialEq)]
 #[command(arg_required_else_help(true))]
@@ -37,12 +38,16 @@ pub enum Files {
     Track(TrackFile),
     /// Remove a file from tracking the version number
     Rm(File),
-    /// Set the version number from a file
+    /// Write the current version number to a tracked file
     Update(File),
+    /// Set the version number from the value in a tracked file
+    Import(File),
     /// Update all files
     UpdateAll,
     /// List tracked files
     List,
+    /// Find occurrences of the version in files that aren't tracked
+    Audit,
 }
 
 impl TryFrom<Files> for Operator {
@@ -50,11 +55,16 @@ impl TryFrom<Files> for Operator {
 
     fn try_from(cmd: Files) -> Result<Self, Self::Error> {
         let op = match cmd {
-            Files::Track(track) => Operator::AddFile(track.try_into()?),
+            Files::Track(track) => {
+                let force = track.force;
+                Operator::AddFile(track.try_into()?, force)
+            }
             Files::Rm(file) => Operator::RmFile(file.to_string()),
             Files::Update(file) => Operator::Update(file.to_string()),
+            Files::Import(file) => Operator::Import(file.to_string()),
             Files::UpdateAll => Operator::UpdateAll,
             Files::List => Operator::ListFiles,
+            Files::Audit => Operator::Audit,
         };
         Ok(op)
     }
@@ -65,11 +75,13 @@ impl TryFrom<&Files> for Operator {
 
     fn try_from(cmd: &Files) -> Result<Self, Self::Error> {
         let op = match cmd {
-            Files::Track(track) => Operator::AddFile(track.try_into()?),
+            Files::Track(track) => Operator::AddFile(track.try_into()?, track.force),
             Files::Rm(file) => Operator::RmFile(file.to_string()),
             Files::Update(file) => Operator::Update(file.to_string()),
+            Files::Import(file) => Operator::Import(file.to_string()),
             Files::UpdateAll => Operator::UpdateAll,
             Files::List => Operator::ListFiles,
+            Files::Audit => Operator::Audit,
         };
         Ok(op)
     }
@@ -83,19 +95,41 @@ pub struct TrackFile {
     pub path: ClioPath,
     /// The expression to match the version number
     ///
-    /// This expression should be a regex with a single capture group that matches the version number
-    pub expr: String,
+    /// This expression should be a regex with a single capture group that matches the version number.
+    /// When omitted, the file is searched for the current version and an expression is generated.
+    pub expr: Option<String>,
+    /// The line to generate an expression for when several lines contain the version
+    #[arg(long, conflicts_with = "expr")]
+    pub line: Option<usize>,
+    /// Replace versions next to `version-manager:` marker comments instead of matching an expression
+    #[arg(long, conflicts_with_all = ["expr", "line"])]
+    pub markers: bool,
+    /// Allow tracking a file outside of the project root
+    #[arg(long)]
+    pub allow_outside_root: bool,
+    /// Track the file even if it is already tracked or the expression doesn't match
+    #[arg(long, short)]
+    pub force: bool,
 }
 
 impl TryFrom<&TrackFile> for SetTypes {
     type Error = VersionError;
 
     fn try_from(track_file: &TrackFile) -> Result<Self, Self::Error> {
-        let track_file = TrackedFiles::new_from_path_and_regex(
-            track_file.path.to_path_buf(),
-            track_file.expr.clone().parse::<Regex>()?,
-        );
-        Ok(SetTypes::NewFile(track_file))
+        let mut tracked = match track_file.expr {
+            Some(ref expr) => TrackedFiles::new_from_path_and_regex(
+                track_file.path.to_path_buf(),
+                expr.parse::<Regex>()?,
+            ),
+            None => TrackedFiles::new_from_path(track_file.path.to_path_buf(), String::new()),
+        };
+        tracked.allow_outside_root = track_file.allow_outside_root;
+        tracked.markers = track_file.markers;
+        match track_file.expr {
+            Some(_) => Ok(SetTypes::NewFile(tracked)),
+            None if track_file.markers => Ok(SetTypes::NewFile(tracked)),
+            None => Ok(SetTypes::SuggestFile(tracked, track_file.line)),
+        }
     }
 }
 
@@ -103,11 +137,7 @@ impl TryFrom<TrackFile> for SetTypes {
     type Error = VersionError;
 
     fn try_from(track_file: TrackFile) -> Result<Self, Self::Error> {
-        let track_file = TrackedFiles::new_from_path_and_regex(
-            track_file.path.to_path_buf(),
-            track_file.expr.clone().parse::<Regex>()?,
-        );
-        Ok(SetTypes::NewFile(track_file))
+        (&track_file).try_into()
     }
 }
 
@@ -119,9 +149,9 @@ pub struct File {
     pub path: ClioPath,
 }
 
-impl ToString for File {
-    fn to_string(&self) -> String {
-        self.path.to_string()
+impl fmt::Display for File {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.path().display())
     }
 }
 
@@ -129,7 +159,7 @@ impl TryFrom<&File> for SetTypes {
     type Error = VersionError;
 
     fn try_from(file: &File) -> Result<Self, Self::Error> {
-        Ok(SetTypes::String(file.path.to_string()))
+        Ok(SetTypes::String(file.to_string()))
     }
 }
 
@@ -137,6 +167,6 @@ impl TryFrom<File> for SetTypes {
     type Error = VersionError;
 
     fn try_from(file: File) -> Result<Self, Self::Error> {
-        Ok(SetTypes::String(file.path.to_string()))
+        Ok(SetTypes::String(file.to_string()))
     }
 }
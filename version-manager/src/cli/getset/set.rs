@@ -17,9 +17,9 @@ impl TryFrom<&Set> for SetTypes {
 
     fn try_from(set: &Set) -> Result<Self, VersionError> {
         if let Some(value) = &set.value {
-            return Ok(SetTypes::Number(*value));
+            Ok(SetTypes::Number(*value))
         } else if let Some(command) = &set.command {
-            return Ok(command.try_into()?);
+            command.try_into()
         } else {
             Err(VersionError::NoValue)
         }
@@ -31,9 +31,9 @@ impl TryFrom<Set> for SetTypes {
 
     fn try_from(set: Set) -> Result<Self, VersionError> {
         if let Some(value) = &set.value {
-            return Ok(SetTypes::Number(*value));
+            Ok(SetTypes::Number(*value))
         } else if let Some(command) = &set.command {
-            return Ok(command.try_into()?);
+            command.try_into()
         } else {
             Err(VersionError::NoValue)
         }
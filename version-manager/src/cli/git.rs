@@ -0,0 +1,55 @@
+use crate::version::Scope;
+use clap::{Args, builder::NonEmptyStringValueParser};
+use std::path::PathBuf;
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct SyncVersion {
+    /// Take the version from the highest matching release tag
+    #[arg(long, required = true)]
+    pub from_git: bool,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct GetVersion {
+    /// Print a development version built from the last release tag, like 1.4.1-dev.7+g3f2a1bc
+    #[arg(long)]
+    pub dev: bool,
+    /// Write the development version into the tracked files, leaving VERSION.toml alone
+    #[arg(long, requires = "dev")]
+    pub write: bool,
+    /// Read the version from VERSION.toml as it was at a git revision
+    #[arg(long, conflicts_with = "dev", value_parser = NonEmptyStringValueParser::new())]
+    pub rev: Option<String>,
+}
+
+impl GetVersion {
+    pub fn scope(&self) -> Scope {
+        match (self.dev, &self.rev) {
+            (true, _) => Scope::DevVersion(self.write),
+            (false, Some(rev)) => Scope::GetAt(rev.clone()),
+            (false, None) => Scope::Get,
+        }
+    }
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct DiffRevisions {
+    /// The revision to compare from
+    #[arg(value_parser = NonEmptyStringValueParser::new())]
+    pub from: String,
+    /// The revision to compare to, defaulting to the working tree
+    #[arg(value_parser = NonEmptyStringValueParser::new())]
+    pub to: Option<String>,
+}
+
+/// Register with `git config merge.version.driver "version merge-driver %O %A %B"` and a
+/// `VERSION.toml merge=version` line in .gitattributes
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct MergeDriver {
+    /// The common ancestor's version of the file, git's %O
+    pub base: PathBuf,
+    /// Our version of the file, which the result is written to, git's %A
+    pub ours: PathBuf,
+    /// Their version of the file, git's %B
+    pub theirs: PathBuf,
+}
@@ -0,0 +1,54 @@
+use crate::version::{HooksOp, Scope};
+use clap::{Args, Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Parser, Debug, Clone, PartialEq)]
+#[command(arg_required_else_help(true))]
+pub struct HooksCommand {
+    #[clap(subcommand)]
+    pub command: Hooks,
+}
+
+#[derive(Subcommand, Debug, Clone, PartialEq)]
+#[command(arg_required_else_help(true))]
+pub enum Hooks {
+    /// Install a pre-commit hook that fails when tracked files drift from VERSION.toml
+    Install(InstallHooks),
+    /// Remove the hooks installed by `version hooks install`
+    Uninstall,
+    /// Check that a commit message follows Conventional Commits, as the commit-msg hook does
+    CheckMessage(CheckMessage),
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct InstallHooks {
+    /// Also install a commit-msg hook that requires Conventional Commit messages
+    #[arg(long)]
+    pub commit_msg: bool,
+    /// Replace existing hooks without asking
+    #[arg(long)]
+    pub force: bool,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct CheckMessage {
+    /// The file holding the commit message
+    pub file: PathBuf,
+}
+
+impl From<HooksCommand> for Scope {
+    fn from(cmd: HooksCommand) -> Self {
+        (&cmd).into()
+    }
+}
+
+impl From<&HooksCommand> for Scope {
+    fn from(cmd: &HooksCommand) -> Self {
+        let op = match cmd.command {
+            Hooks::Install(ref install) => HooksOp::Install(install.commit_msg, install.force),
+            Hooks::Uninstall => HooksOp::Uninstall,
+            Hooks::CheckMessage(ref check) => HooksOp::CheckMessage(check.file.clone()),
+        };
+        Scope::Hooks(op)
+    }
+}
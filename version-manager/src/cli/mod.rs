@@ -1,6 +1,10 @@
+pub mod change;
+pub mod changelog;
 pub mod command;
 pub mod files;
 pub mod getset;
+pub mod git;
+pub mod hooks;
 pub mod package;
 pub mod version;
 
@@ -1,23 +1,55 @@
 use super::{
+    changelog::ChangelogCommand,
     files::FilesCommand,
     getset::{GetSet, GetSetBuild, GetSetRm, SetVer},
+    git::{GetVersion, SyncVersion},
 };
-use crate::{VersionError, version::Scope};
-use clap::{Parser, Subcommand};
+use crate::{
+    VersionError,
+    version::{Bump, Scope, Selector},
+};
+use clap::{Args, Parser, Subcommand, builder::NonEmptyStringValueParser};
 
 #[derive(Parser, Debug, Clone, PartialEq)]
 #[command(arg_required_else_help(true))]
 pub struct PackageCommand {
-    /// The package name to track
+    /// The package name to track, inferred from the current directory when omitted
     pub package_name: Option<String>,
+    /// Apply the operation to every package
+    #[arg(long, conflicts_with_all = ["package_name", "matches", "group"])]
+    pub all: bool,
+    /// Apply the operation to every package whose name matches a glob, like 'sdk-*'
+    #[arg(long = "match", value_name = "GLOB", conflicts_with_all = ["package_name", "group"])]
+    pub matches: Option<String>,
+    /// Apply the operation to every member of a package group
+    #[arg(long, conflicts_with = "package_name")]
+    pub group: Option<String>,
     #[clap(subcommand)]
     pub command: PackageOperation,
 }
 
+impl PackageCommand {
+    /// The packages chosen with `--all`, `--match`, or `--group`, if any
+    pub fn selector(&self) -> Option<Selector> {
+        if self.all {
+            Some(Selector::All)
+        } else if let Some(ref glob) = self.matches {
+            Some(Selector::Match(glob.clone()))
+        } else {
+            self.group
+                .as_ref()
+                .map(|group| Selector::Group(group.clone()))
+        }
+    }
+}
+
 impl TryFrom<PackageCommand> for Scope {
     type Error = VersionError;
 
     fn try_from(cmd: PackageCommand) -> Result<Self, Self::Error> {
+        if let Some(selector) = cmd.selector() {
+            return Ok(Scope::Packages(selector, Box::new(cmd.command.try_into()?)));
+        }
         Ok(Scope::Package(
             (&cmd).try_into()?,
             Box::new(cmd.command.try_into()?),
@@ -29,6 +61,12 @@ impl TryFrom<&PackageCommand> for Scope {
     type Error = VersionError;
 
     fn try_from(cmd: &PackageCommand) -> Result<Self, Self::Error> {
+        if let Some(selector) = cmd.selector() {
+            return Ok(Scope::Packages(
+                selector,
+                Box::new((&cmd.command).try_into()?),
+            ));
+        }
         Ok(Scope::Package(
             cmd.try_into()?,
             Box::new((&cmd.command).try_into()?),
@@ -40,15 +78,13 @@ impl TryFrom<PackageCommand> for String {
     type Error = VersionError;
 
     fn try_from(cmd: PackageCommand) -> Result<Self, Self::Error> {
-        match cmd.package_name {
-            Some(ref name) => Ok(name.clone()),
-            None => {
-                if cmd.command == PackageOperation::List {
-                    Ok("".to_string())
-                } else {
-                    return Err(VersionError::PackageNameRequired);
-                }
-            }
+        match (&cmd.command, &cmd.package_name) {
+            (PackageOperation::Add(add), _) => Ok(add.name.clone()),
+            (PackageOperation::Rename(rename), _) => Ok(rename.from.clone()),
+            (PackageOperation::Copy(copy), _) => Ok(copy.from.clone()),
+            (_, Some(name)) => Ok(name.clone()),
+            // Inferred from the working directory when the package is run
+            (_, None) => Ok("".to_string()),
         }
     }
 }
@@ -57,15 +93,13 @@ impl TryFrom<&PackageCommand> for String {
     type Error = VersionError;
 
     fn try_from(cmd: &PackageCommand) -> Result<Self, Self::Error> {
-        match cmd.package_name {
-            Some(ref name) => Ok(name.clone()),
-            None => {
-                if cmd.command == PackageOperation::List {
-                    Ok("".to_string())
-                } else {
-                    return Err(VersionError::PackageNameRequired);
-                }
-            }
+        match (&cmd.command, &cmd.package_name) {
+            (PackageOperation::Add(add), _) => Ok(add.name.clone()),
+            (PackageOperation::Rename(rename), _) => Ok(rename.from.clone()),
+            (PackageOperation::Copy(copy), _) => Ok(copy.from.clone()),
+            (_, Some(name)) => Ok(name.clone()),
+            // Inferred from the working directory when the package is run
+            (_, None) => Ok("".to_string()),
         }
     }
 }
@@ -88,7 +122,7 @@ pub enum PackageOperation {
     /// Change the build identifier
     Build(GetSetBuild),
     /// Get the current version number as a full SemVer string
-    Get,
+    Get(GetVersion),
     /// Set the version number to a specific version
     Set(SetVer),
     /// Get just the version number as a string with no revision or build identifiers
@@ -97,10 +131,65 @@ pub enum PackageOperation {
     Revision,
     /// Track and update the version number in a file
     File(FilesCommand),
+    /// Maintain the package's Keep a Changelog formatted changelog
+    Changelog(ChangelogCommand),
     /// Remove a package
     Rm,
     /// List tracked packages
     List,
+    /// Start tracking a new package
+    Add(AddPackage),
+    /// Rename a package, updating everything that refers to it
+    Rename(RenamePackage),
+    /// Create a new package with the version and settings of an existing one
+    Copy(RenamePackage),
+    /// List the packages with changes since their last release tag
+    Changed(Since),
+    /// Bump every package with changes since its last release tag
+    BumpChanged(BumpChanged),
+    /// Set the version from somewhere else
+    Sync(SyncVersion),
+    /// Check that the package's release tag on HEAD matches VERSION.toml
+    VerifyTag,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct Since {
+    /// Compare every package against this git ref instead of its release tag
+    #[arg(long)]
+    pub since: Option<String>,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct BumpChanged {
+    /// The version number to bump
+    #[arg(value_enum)]
+    pub level: Bump,
+    #[command(flatten)]
+    pub since: Since,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct AddPackage {
+    /// The name of the new package
+    #[arg(value_parser = NonEmptyStringValueParser::new())]
+    pub name: String,
+    /// The initial version of the package
+    #[arg(long, default_value = "0.1.0")]
+    pub version: String,
+    /// The directory the package lives in, relative to the project root
+    #[arg(long)]
+    pub path: Option<String>,
+}
+
+#[derive(Args, Debug, Clone, PartialEq)]
+pub struct RenamePackage {
+    /// The existing package
+    #[arg(value_parser = NonEmptyStringValueParser::new())]
+    pub from: String,
+    /// The new package name
+    #[arg(value_parser = NonEmptyStringValueParser::new())]
+    pub to: String,
 }
 
 impl TryFrom<PackageOperation> for Scope {
@@ -115,13 +204,23 @@ impl TryFrom<PackageOperation> for Scope {
             PackageOperation::Beta(getset) => Scope::Beta(getset.try_into()?),
             PackageOperation::RC(getset) => Scope::RC(getset.try_into()?),
             PackageOperation::Build(getset) => Scope::Build(getset.try_into()?),
-            PackageOperation::Get => Scope::Get,
+            PackageOperation::Get(get) => get.scope(),
             PackageOperation::Version => Scope::Version,
             PackageOperation::Revision => Scope::Revision,
             PackageOperation::File(file_cmd) => Scope::File(file_cmd.try_into()?),
+            PackageOperation::Changelog(changelog_cmd) => changelog_cmd.try_into()?,
+            PackageOperation::Sync(_) => Scope::SyncFromGit,
+            PackageOperation::VerifyTag => Scope::VerifyTag,
             PackageOperation::Rm => Scope::RmPackage,
             PackageOperation::List => Scope::ListPackages,
             PackageOperation::Set(setver) => Scope::Set(setver.try_into()?),
+            PackageOperation::Add(add) => Scope::AddPackage(add.version.clone(), add.path.clone()),
+            PackageOperation::Rename(rename) => Scope::RenamePackage(rename.to.clone()),
+            PackageOperation::Copy(copy) => Scope::CopyPackage(copy.to.clone()),
+            PackageOperation::Changed(changed) => Scope::ChangedPackages(changed.since.clone()),
+            PackageOperation::BumpChanged(bump) => {
+                Scope::BumpChanged(bump.level, bump.since.since.clone())
+            }
         };
         Ok(scope)
     }
@@ -139,13 +238,23 @@ impl TryFrom<&PackageOperation> for Scope {
             PackageOperation::Beta(getset) => Scope::Beta(getset.try_into()?),
             PackageOperation::RC(getset) => Scope::RC(getset.try_into()?),
             PackageOperation::Build(getset) => Scope::Build(getset.try_into()?),
-            PackageOperation::Get => Scope::Get,
+            PackageOperation::Get(get) => get.scope(),
             PackageOperation::Version => Scope::Version,
             PackageOperation::Revision => Scope::Revision,
             PackageOperation::File(file_cmd) => Scope::File(file_cmd.try_into()?),
+            PackageOperation::Changelog(changelog_cmd) => changelog_cmd.try_into()?,
+            PackageOperation::Sync(_) => Scope::SyncFromGit,
+            PackageOperation::VerifyTag => Scope::VerifyTag,
             PackageOperation::Rm => Scope::RmPackage,
             PackageOperation::List => Scope::ListPackages,
             PackageOperation::Set(setver) => Scope::Set(setver.try_into()?),
+            PackageOperation::Add(add) => Scope::AddPackage(add.version.clone(), add.path.clone()),
+            PackageOperation::Rename(rename) => Scope::RenamePackage(rename.to.clone()),
+            PackageOperation::Copy(copy) => Scope::CopyPackage(copy.to.clone()),
+            PackageOperation::Changed(changed) => Scope::ChangedPackages(changed.since.clone()),
+            PackageOperation::BumpChanged(bump) => {
+                Scope::BumpChanged(bump.level, bump.since.since.clone())
+            }
         };
         Ok(scope)
     }
@@ -1,8 +1,12 @@
 use crate::{
     VersionError,
     cli::{
+        change::ChangeCommand,
+        changelog::ChangelogCommand,
         files::FilesCommand,
         getset::{GetSet, GetSetBuild, GetSetRm, SetVer},
+        git::{DiffRevisions, GetVersion, MergeDriver, SyncVersion},
+        hooks::HooksCommand,
         package::PackageCommand,
     },
     version::Scope,
@@ -28,7 +32,7 @@ pub enum VersionCommand {
     /// Change the build identifier
     Build(GetSetBuild),
     /// Get the current version number as a full SemVer string
-    Get,
+    Get(GetVersion),
     /// Set the version number to a specific version
     Set(SetVer),
     /// Get just the version number as a string with no revision or build identifiers
@@ -39,6 +43,26 @@ pub enum VersionCommand {
     File(FilesCommand),
     /// Track and update the version number in a file
     Package(PackageCommand),
+    /// Check that every tracked file matches the version in VERSION.toml
+    Check,
+    /// Record pending changes to release later
+    Change(ChangeCommand),
+    /// Apply every pending change, bumping versions and writing changelog entries
+    Release,
+    /// Maintain a Keep a Changelog formatted CHANGELOG.md
+    Changelog(ChangelogCommand),
+    /// Set the version from somewhere else
+    Sync(SyncVersion),
+    /// Check that the release tags on HEAD match VERSION.toml
+    #[command(name = "verify-tag")]
+    VerifyTag,
+    /// Compare the versions and tracked files in VERSION.toml between git revisions
+    Diff(DiffRevisions),
+    /// Merge VERSION.toml three ways, for use as a git merge driver
+    #[command(name = "merge-driver")]
+    MergeDriver(MergeDriver),
+    /// Manage the git hooks that keep commits in line with VERSION.toml
+    Hooks(HooksCommand),
 }
 
 impl TryFrom<&VersionCommand> for Scope {
@@ -53,12 +77,25 @@ impl TryFrom<&VersionCommand> for Scope {
             VersionCommand::Beta(getset) => Scope::Beta(getset.try_into()?),
             VersionCommand::RC(getset) => Scope::RC(getset.try_into()?),
             VersionCommand::Build(getset) => Scope::Build(getset.try_into()?),
-            VersionCommand::Get => Scope::Get,
+            VersionCommand::Get(get) => get.scope(),
             VersionCommand::Version => Scope::Version,
             VersionCommand::Revision => Scope::Revision,
             VersionCommand::File(file_cmd) => Scope::File(file_cmd.try_into()?),
             VersionCommand::Package(package_cmd) => package_cmd.try_into()?,
             VersionCommand::Set(setver) => Scope::Set(setver.try_into()?),
+            VersionCommand::Check => Scope::Check,
+            VersionCommand::Change(change_cmd) => change_cmd.try_into()?,
+            VersionCommand::Release => Scope::Release,
+            VersionCommand::Changelog(changelog_cmd) => changelog_cmd.try_into()?,
+            VersionCommand::Sync(_) => Scope::SyncFromGit,
+            VersionCommand::VerifyTag => Scope::VerifyTag,
+            VersionCommand::Diff(diff) => Scope::Diff(diff.from.clone(), diff.to.clone()),
+            VersionCommand::MergeDriver(driver) => Scope::MergeDriver(
+                driver.base.clone(),
+                driver.ours.clone(),
+                driver.theirs.clone(),
+            ),
+            VersionCommand::Hooks(hooks_cmd) => hooks_cmd.into(),
         };
         Ok(scope)
     }
@@ -76,12 +113,25 @@ impl TryFrom<VersionCommand> for Scope {
             VersionCommand::Beta(getset) => Scope::Beta(getset.try_into()?),
             VersionCommand::RC(getset) => Scope::RC(getset.try_into()?),
             VersionCommand::Build(getset) => Scope::Build(getset.try_into()?),
-            VersionCommand::Get => Scope::Get,
+            VersionCommand::Get(get) => get.scope(),
             VersionCommand::Version => Scope::Version,
             VersionCommand::Revision => Scope::Revision,
             VersionCommand::File(file_cmd) => Scope::File(file_cmd.try_into()?),
             VersionCommand::Package(package_cmd) => package_cmd.try_into()?,
             VersionCommand::Set(setver) => Scope::Set(setver.try_into()?),
+            VersionCommand::Check => Scope::Check,
+            VersionCommand::Change(change_cmd) => change_cmd.try_into()?,
+            VersionCommand::Release => Scope::Release,
+            VersionCommand::Changelog(changelog_cmd) => changelog_cmd.try_into()?,
+            VersionCommand::Sync(_) => Scope::SyncFromGit,
+            VersionCommand::VerifyTag => Scope::VerifyTag,
+            VersionCommand::Diff(diff) => Scope::Diff(diff.from.clone(), diff.to.clone()),
+            VersionCommand::MergeDriver(driver) => Scope::MergeDriver(
+                driver.base.clone(),
+                driver.ours.clone(),
+                driver.theirs.clone(),
+            ),
+            VersionCommand::Hooks(hooks_cmd) => hooks_cmd.into(),
         };
         Ok(scope)
     }
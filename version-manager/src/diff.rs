@@ -0,0 +1,122 @@
+use crate::{
+    changes::ROOT,
+    files::{TrackedFiles, VersionFile},
+};
+use semver::Version;
+use std::collections::BTreeSet;
+
+/// Describe how the versions and tracked files changed between two revisions of VERSION.toml
+///
+/// The root comes first, then every package in either revision. Anything without changes is
+/// left out.
+pub fn diff(old: &VersionFile, new: &VersionFile) -> Vec<String> {
+    let mut lines = describe(
+        ROOT,
+        Some(&old.version),
+        Some(&new.version),
+        &old.files,
+        &new.files,
+    );
+    let names: BTreeSet<&String> = old.package.keys().chain(new.package.keys()).collect();
+    for name in names {
+        let (old_pkg, new_pkg) = (old.package.get(name), new.package.get(name));
+        lines.extend(describe(
+            name,
+            old_pkg.map(|pkg| &pkg.version),
+            new_pkg.map(|pkg| &pkg.version),
+            old_pkg.map_or(&[], |pkg| &pkg.files),
+            new_pkg.map_or(&[], |pkg| &pkg.files),
+        ));
+    }
+    lines
+}
+
+fn describe(
+    name: &str,
+    old: Option<&Version>,
+    new: Option<&Version>,
+    old_files: &[TrackedFiles],
+    new_files: &[TrackedFiles],
+) -> Vec<String> {
+    let files = files_diff(old_files, new_files);
+    let heading = match (old, new) {
+        (None, Some(new)) => format!("{}: added at {}", name, new),
+        (Some(old), None) => format!("{}: removed, was {}", name, old),
+        (Some(old), Some(new)) if old != new => format!("{}: {} -> {}", name, old, new),
+        (Some(version), Some(_)) if !files.is_empty() => format!("{}: {}", name, version),
+        _ => return vec![],
+    };
+    let mut lines = vec![heading];
+    lines.extend(files.into_iter().map(|line| format!("  {}", line)));
+    lines
+}
+
+/// The tracked files added, removed or changed between two lists
+fn files_diff(old: &[TrackedFiles], new: &[TrackedFiles]) -> Vec<String> {
+    let mut lines = vec![];
+    for file in new.iter() {
+        match old.iter().find(|f| f.file == file.file) {
+            None => lines.push(format!("now tracking {}", file.file)),
+            Some(before) if before != file => {
+                lines.push(format!("changed how {} is tracked", file.file))
+            }
+            Some(_) => (),
+        }
+    }
+    for file in old.iter() {
+        if !new.iter().any(|f| f.file == file.file) {
+            lines.push(format!("no longer tracking {}", file.file));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::files::Package;
+
+    #[test]
+    fn describes_changes() {
+        let mut old = VersionFile::default();
+        old.files.push(TrackedFiles::new(
+            "Cargo.toml".to_string(),
+            "(.*)".to_string(),
+        ));
+        old.package.insert("cli".to_string(), Package::default());
+        old.package.insert("gone".to_string(), Package::default());
+        old.package.insert("same".to_string(), Package::default());
+
+        let mut new = old.clone();
+        new.version = Version::new(0, 2, 0);
+        new.files[0].expr = "version = \"(.*)\"".to_string();
+        new.files.push(TrackedFiles::new(
+            "README.md".to_string(),
+            "(.*)".to_string(),
+        ));
+        new.package.remove("gone");
+        new.package.insert("docs".to_string(), Package::default());
+        new.package
+            .get_mut("cli")
+            .unwrap()
+            .files
+            .push(TrackedFiles::new(
+                "cli.toml".to_string(),
+                "(.*)".to_string(),
+            ));
+
+        assert_eq!(
+            diff(&old, &new),
+            vec![
+                "root: 0.1.0 -> 0.2.0",
+                "  changed how Cargo.toml is tracked",
+                "  now tracking README.md",
+                "cli: 0.1.0",
+                "  now tracking cli.toml",
+                "docs: added at 0.1.0",
+                "gone: removed, was 0.1.0",
+            ]
+        );
+        assert!(diff(&old, &old).is_empty());
+    }
+}
@@ -1,26 +1,82 @@
-use crate::{VersionError, VersionResult};
+use crate::{
+    VersionError, VersionResult,
+    changelog::ChangelogConfig,
+    git::GitConfig,
+    graph,
+    hooks::BumpHooks,
+    markers,
+    version::{Bump, Selector},
+};
 use regex::Regex;
 use semver::Version;
 use serde::{Deserialize, Serialize};
 use std::{
     collections::BTreeMap,
-    env,
     fs::{File, OpenOptions, remove_file, rename},
     io::{BufRead, BufReader, BufWriter, Lines, Read, Write},
-    path::PathBuf,
+    path::{Component, Path, PathBuf},
 };
 
+/// The current version of every package, keyed by name
+pub type PackageVersions = BTreeMap<String, Version>;
+
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, PartialOrd)]
 pub struct VersionFile {
     pub version: Version,
     pub files: Vec<TrackedFiles>,
     pub package: BTreeMap<String, Package>,
+    /// Named sets of packages that always share one version
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub groups: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "ChangelogConfig::is_empty")]
+    pub changelog: ChangelogConfig,
+    #[serde(default, skip_serializing_if = "GitConfig::is_empty")]
+    pub git: GitConfig,
+    /// Commands run before and after the root version changes
+    #[serde(default, skip_serializing_if = "BumpHooks::is_empty")]
+    pub hooks: BumpHooks,
+    /// The directory containing VERSION.toml, which tracked paths are resolved against
+    #[serde(skip)]
+    pub root: PathBuf,
 }
 
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, PartialOrd)]
+#[serde(from = "RawPackage", into = "RawPackage")]
 pub struct Package {
+    /// The package's version, resolved from `inherit` when it has one
     pub version: Version,
+    /// `root` or the name of the package this one takes its version from
+    pub inherit: Option<String>,
+    /// The directory the package lives in, relative to the project root, which its tracked
+    /// files are resolved against
+    pub path: Option<String>,
+    /// The package's changelog, relative to its directory
+    pub changelog: Option<String>,
+    /// The template for the package's release tags, overriding `git.package_tag`
+    pub tag: Option<String>,
     pub files: Vec<TrackedFiles>,
+    /// Packages whose bumps cascade into this one
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub depends_on: Vec<String>,
+    /// How to bump this package when a dependency is bumped, a patch bump if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cascade: Option<Bump>,
+    /// Commands run in the package directory before and after its version changes
+    #[serde(default, skip_serializing_if = "BumpHooks::is_empty")]
+    pub hooks: BumpHooks,
+    /// The package directory, or the project root without a `path`
+    #[serde(skip)]
+    pub root: PathBuf,
+}
+
+impl Package {
+    /// Resolve the package directory against the project root
+    pub fn set_root(&mut self, root: &Path) {
+        self.root = match self.path {
+            Some(ref path) => normalize(&root.join(path)),
+            None => root.to_path_buf(),
+        };
+    }
 }
 
 impl Default for VersionFile {
@@ -29,6 +85,11 @@ impl Default for VersionFile {
             version: Version::new(0, 1, 0),
             files: vec![],
             package: BTreeMap::new(),
+            groups: BTreeMap::new(),
+            changelog: ChangelogConfig::default(),
+            git: GitConfig::default(),
+            hooks: BumpHooks::default(),
+            root: PathBuf::new(),
         }
     }
 }
@@ -37,7 +98,83 @@ impl Default for Package {
     fn default() -> Self {
         Package {
             version: Version::new(0, 1, 0),
+            inherit: None,
+            path: None,
+            changelog: None,
+            tag: None,
             files: vec![],
+            depends_on: vec![],
+            cascade: None,
+            hooks: BumpHooks::default(),
+            root: PathBuf::new(),
+        }
+    }
+}
+
+/// A package's `version`, either its own or `{ inherit = "root" }`
+#[derive(Deserialize, Serialize, Clone, Debug, PartialEq)]
+#[serde(untagged)]
+enum VersionSpec {
+    Fixed(Version),
+    Inherit { inherit: String },
+}
+
+/// The on-disk form of a [`Package`]
+#[derive(Deserialize, Serialize)]
+struct RawPackage {
+    version: VersionSpec,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    path: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    changelog: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    tag: Option<String>,
+    files: Vec<TrackedFiles>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    depends_on: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    cascade: Option<Bump>,
+    #[serde(default, skip_serializing_if = "BumpHooks::is_empty")]
+    hooks: BumpHooks,
+}
+
+impl From<RawPackage> for Package {
+    fn from(raw: RawPackage) -> Self {
+        let (version, inherit) = match raw.version {
+            VersionSpec::Fixed(version) => (version, None),
+            // Resolved once the whole file is loaded
+            VersionSpec::Inherit { inherit } => (Version::new(0, 0, 0), Some(inherit)),
+        };
+        Package {
+            version,
+            inherit,
+            path: raw.path,
+            changelog: raw.changelog,
+            tag: raw.tag,
+            files: raw.files,
+            depends_on: raw.depends_on,
+            cascade: raw.cascade,
+            hooks: raw.hooks,
+            root: PathBuf::new(),
+        }
+    }
+}
+
+impl From<Package> for RawPackage {
+    fn from(pkg: Package) -> Self {
+        let version = match pkg.inherit {
+            Some(inherit) => VersionSpec::Inherit { inherit },
+            None => VersionSpec::Fixed(pkg.version),
+        };
+        RawPackage {
+            version,
+            path: pkg.path,
+            changelog: pkg.changelog,
+            tag: pkg.tag,
+            files: pkg.files,
+            depends_on: pkg.depends_on,
+            cascade: pkg.cascade,
+            hooks: pkg.hooks,
         }
     }
 }
@@ -50,13 +187,67 @@ pub trait ModifyTrackedFiles {
     fn add_tracked_file(&mut self, file: TrackedFiles) -> VersionResult<()>;
     fn remove_tracked_file(&mut self, file: PathBuf) -> VersionResult<()>;
     fn update_file(&self, file: PathBuf) -> VersionResult<()>;
+    /// Validate a new tracked file against the file on disk before adding it
+    ///
+    /// Returns the line the expression matched. Duplicate paths and expressions that match
+    /// nothing are refused unless `force` is set.
+    fn track_file(&mut self, file: TrackedFiles, force: bool) -> VersionResult<Option<FileMatch>> {
+        if !file.markers && Regex::new(&file.expr)?.captures_len() != 2 {
+            return Err(VersionError::InvalidExpression(file.expr));
+        }
+        let path = PathBuf::from(&file.file);
+        if !force && self.list_tracked_files()?.iter().any(|f| f == path) {
+            return Err(VersionError::AlreadyTracked(file.file));
+        }
+        let found = match file.markers {
+            true => file.find_marker(self.root())?,
+            false => file.find_match(self.root())?,
+        };
+        if found.is_none() && !force {
+            return match file.markers {
+                true => Err(VersionError::InvalidMarker(format!(
+                    "no markers in {}",
+                    file.file
+                ))),
+                false => Err(VersionError::NoMatch(file.file)),
+            };
+        }
+        self.add_tracked_file(file)?;
+        Ok(found)
+    }
+    fn import_file(&self, file: PathBuf) -> VersionResult<Version> {
+        let root = self.root().to_path_buf();
+        let tracked = match self.list_tracked_files()?.into_iter().find(|f| f == file) {
+            Some(tracked) => tracked,
+            None => {
+                return Err(VersionError::UntrackedFile(
+                    file.to_string_lossy().to_string(),
+                ));
+            }
+        };
+        if tracked.markers {
+            return match markers::find_version(&tracked.read_all(&root)?) {
+                Some(found) => Ok(Version::parse(&found.value)?),
+                None => Err(VersionError::InvalidMarker(format!(
+                    "{}: no version marker to import from",
+                    tracked.file
+                ))),
+            };
+        }
+        match tracked.find_match(&root)? {
+            Some(found) => Ok(Version::parse(found.value.trim())?),
+            None => Err(VersionError::NoMatch(tracked.file)),
+        }
+    }
     fn list_tracked_files(&self) -> VersionResult<Vec<TrackedFiles>>;
+    fn root(&self) -> &Path;
 }
 
 impl ModifyTrackedFiles for VersionFile {
     fn update_tracked_files(&self) -> VersionResult<()> {
+        let packages = self.package_versions();
         for file in self.files.iter() {
-            file.update(self.version.to_string())?;
+            file.update(&self.version, Some(&packages), &self.root)?;
         }
         Ok(())
     }
@@ -74,21 +265,25 @@ impl ModifyTrackedFiles for VersionFile {
     fn update_file(&self, file: PathBuf) -> VersionResult<()> {
         for f in self.files.iter() {
             if f == file {
-                f.update(self.version.to_string())?;
+                f.update(&self.version, Some(&self.package_versions()), &self.root)?;
             }
         }
         Ok(())
     }
 
     fn list_tracked_files(&self) -> VersionResult<Vec<TrackedFiles>> {
-        return Ok(self.files.clone());
+        Ok(self.files.clone())
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
     }
 }
 
 impl ModifyTrackedFiles for Package {
     fn update_tracked_files(&self) -> VersionResult<()> {
         for file in self.files.iter() {
-            file.update(self.version.to_string())?;
+            file.update(&self.version, None, &self.root)?;
         }
         Ok(())
     }
@@ -106,34 +301,146 @@ impl ModifyTrackedFiles for Package {
     fn update_file(&self, file: PathBuf) -> VersionResult<()> {
         for f in self.files.iter() {
             if f == file {
-                f.update(self.version.to_string())?;
+                f.update(&self.version, None, &self.root)?;
             }
         }
         Ok(())
     }
 
     fn list_tracked_files(&self) -> VersionResult<Vec<TrackedFiles>> {
-        return Ok(self.files.clone());
+        Ok(self.files.clone())
+    }
+
+    fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl Package {
+    /// Update tracked files with every package version known, so package markers resolve
+    pub fn update_tracked_files_with(&self, packages: &PackageVersions) -> VersionResult<()> {
+        for file in self.files.iter() {
+            file.update(&self.version, Some(packages), &self.root)?;
+        }
+        Ok(())
     }
 }
 
 impl VersionFile {
+    pub fn package_versions(&self) -> PackageVersions {
+        self.package
+            .iter()
+            .map(|(name, pkg)| (name.clone(), pkg.version.clone()))
+            .collect()
+    }
+
     pub fn get_package(&self, name: &str) -> VersionResult<&Package> {
-        if let Some(ref pkg) = self.package.get(name) {
-            return Ok(pkg);
+        match self.package.get(name) {
+            Some(pkg) => Ok(pkg),
+            None => Err(unknown_package(name, &self.package)),
         }
-        Err(VersionError::InvalidCommand)
     }
 
     pub fn get_package_mut(&mut self, name: &str) -> VersionResult<&mut Package> {
-        if let Some(pkg) = self.package.get_mut(name) {
-            return Ok(pkg);
+        // The suggestion needs the other names, which can't be borrowed in the same match as
+        // a returned mutable borrow, so a miss is reported before looking up for real
+        if !self.package.contains_key(name) {
+            return Err(unknown_package(name, &self.package));
+        }
+        match self.package.get_mut(name) {
+            Some(pkg) => Ok(pkg),
+            None => Err(VersionError::UnknownPackage(name.to_string())),
         }
-        Err(VersionError::InvalidCommand)
+    }
+
+    /// The names of the packages `selector` picks
+    pub fn select(&self, selector: &Selector) -> VersionResult<Vec<String>> {
+        match selector {
+            Selector::All => Ok(self.package.keys().cloned().collect()),
+            Selector::Match(glob) => {
+                let mut expr = String::from("^");
+                for c in glob.chars() {
+                    match c {
+                        '*' => expr.push_str(".*"),
+                        '?' => expr.push('.'),
+                        c => expr.push_str(&regex::escape(&c.to_string())),
+                    }
+                }
+                expr.push('$');
+                let re = Regex::new(&expr)?;
+                Ok(self
+                    .package
+                    .keys()
+                    .filter(|name| re.is_match(name))
+                    .cloned()
+                    .collect())
+            }
+            Selector::Group(group) => match self.groups.get(group) {
+                Some(members) => Ok(members.clone()),
+                None => Err(VersionError::InvalidGroup(format!(
+                    "no group named {}",
+                    group
+                ))),
+            },
+        }
+    }
+
+    /// An unknown package error, suggesting the closest known name when there is one
+    pub fn unknown_package(&self, name: &str) -> VersionError {
+        unknown_package(name, &self.package)
+    }
+
+    /// Start tracking a new package
+    pub fn add_package(&mut self, name: &str, mut pkg: Package) -> VersionResult<()> {
+        if self.package.contains_key(name) {
+            return Err(VersionError::PackageExists(name.to_string()));
+        }
+        pkg.set_root(&self.root);
+        self.package.insert(name.to_string(), pkg);
+        Ok(())
+    }
+
+    /// Rename a package, along with every dependency, inheritance, and group entry naming it
+    pub fn rename_package(&mut self, from: &str, to: &str) -> VersionResult<()> {
+        let pkg = self.get_package(from)?.clone();
+        self.add_package(to, pkg)?;
+        self.package.remove(from);
+        let rename = |name: &mut String| {
+            if name == from {
+                *name = to.to_string();
+            }
+        };
+        for pkg in self.package.values_mut() {
+            pkg.depends_on.iter_mut().for_each(rename);
+            pkg.inherit.iter_mut().for_each(rename);
+        }
+        for members in self.groups.values_mut() {
+            members.iter_mut().for_each(rename);
+        }
+        Ok(())
+    }
+
+    /// Create a package with the version and settings of an existing one
+    ///
+    /// Tracked files aren't copied, as a file can only be tracked once.
+    pub fn copy_package(&mut self, from: &str, to: &str) -> VersionResult<()> {
+        let pkg = Package {
+            files: vec![],
+            ..self.get_package(from)?.clone()
+        };
+        self.add_package(to, pkg)
     }
 
     pub fn load(version_file: PathBuf) -> VersionResult<Self> {
-        let ver: Self = match File::open(version_file.clone()) {
+        let root = match version_file.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let root = match std::path::absolute(root) {
+            Ok(root) => normalize(&root),
+            Err(e) => return Err(VersionError::IoError(e)),
+        };
+        let mut ver: Self = match File::open(version_file.clone()) {
             Ok(mut file) => {
                 let mut contents = String::new();
                 match file.read_to_string(&mut contents) {
@@ -152,10 +459,52 @@ impl VersionFile {
                 Err(e) => return Err(VersionError::IoError(e)),
             },
         };
+        ver.set_root(root);
+        graph::resolve_inherited(&mut ver)?;
 
         Ok(ver)
     }
 
+    /// Parse the contents of a VERSION.toml, like an older revision of it, for the project at
+    /// `root`
+    pub fn parse(contents: &str, root: PathBuf) -> VersionResult<Self> {
+        let mut ver: Self = toml::from_str(contents)?;
+        ver.set_root(root);
+        graph::resolve_inherited(&mut ver)?;
+        Ok(ver)
+    }
+
+    /// Set the project root for the version file and every package in it
+    pub fn set_root(&mut self, root: PathBuf) {
+        for pkg in self.package.values_mut() {
+            pkg.set_root(&root);
+        }
+        self.root = root;
+    }
+
+    /// Find the VERSION.toml for `dir`, searching up through its parents
+    ///
+    /// Falls back to `dir/VERSION.toml`, which is created on load, when none is found.
+    pub fn discover(dir: &Path) -> PathBuf {
+        for ancestor in dir.ancestors() {
+            let candidate = ancestor.join("VERSION.toml");
+            if candidate.is_file() {
+                return candidate;
+            }
+        }
+        dir.join("VERSION.toml")
+    }
+
+    /// The package whose directory contains `dir`, preferring the most deeply nested one
+    pub fn package_at(&self, dir: &Path) -> Option<String> {
+        let dir = normalize(dir);
+        self.package
+            .iter()
+            .filter(|(_, pkg)| pkg.path.is_some() && dir.starts_with(&pkg.root))
+            .max_by_key(|(_, pkg)| pkg.root.components().count())
+            .map(|(name, _)| name.clone())
+    }
+
     pub fn save(&mut self, version_file: PathBuf) -> VersionResult<()> {
         self.save_version(version_file)?;
         self.save_files()?;
@@ -190,8 +539,9 @@ impl VersionFile {
 
     fn save_files(&self) -> VersionResult<()> {
         self.sync_files()?;
-        for (_, pkg) in self.package.iter() {
-            pkg.sync_files()?;
+        let packages = self.package_versions();
+        for pkg in self.package.values() {
+            pkg.update_tracked_files_with(&packages)?;
         }
         Ok(())
     }
@@ -200,18 +550,32 @@ impl VersionFile {
 #[derive(Deserialize, Serialize, Clone, Debug, PartialEq, PartialOrd)]
 pub struct TrackedFiles {
     pub file: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     pub expr: String,
+    /// Replace versions next to `version-manager:` marker comments instead of using `expr`
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub markers: bool,
+    /// Allow the file to live outside of the project root
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub allow_outside_root: bool,
 }
 
 impl TrackedFiles {
     pub fn new(file: String, expr: String) -> Self {
-        TrackedFiles { file, expr }
+        TrackedFiles {
+            file,
+            expr,
+            allow_outside_root: false,
+            markers: false,
+        }
     }
 
     pub fn new_from_path(file: PathBuf, expr: String) -> Self {
         TrackedFiles {
             file: file.to_string_lossy().to_string(),
             expr,
+            allow_outside_root: false,
+            markers: false,
         }
     }
 
@@ -219,6 +583,8 @@ impl TrackedFiles {
         TrackedFiles {
             file,
             expr: expr.as_str().to_string(),
+            allow_outside_root: false,
+            markers: false,
         }
     }
 
@@ -226,40 +592,49 @@ impl TrackedFiles {
         TrackedFiles {
             file: file.to_string_lossy().to_string(),
             expr: expr.as_str().to_string(),
+            allow_outside_root: false,
+            markers: false,
         }
     }
 
-    pub fn open(&self) -> VersionResult<File> {
-        let cd = match env::current_dir() {
-            Ok(cd) => cd,
-            Err(e) => return Err(VersionError::IoError(e)),
-        };
-        let path = cd.join(&self.file);
+    /// Resolve the tracked path against the project root
+    ///
+    /// Absolute paths and paths that climb out of the root with `..` are rejected
+    /// unless `allow_outside_root` is set.
+    pub fn path(&self, root: &Path) -> VersionResult<PathBuf> {
+        let root = normalize(root);
+        let path = normalize(&root.join(&self.file));
+        if !self.allow_outside_root
+            && (Path::new(&self.file).is_absolute() || !path.starts_with(&root))
+        {
+            return Err(VersionError::OutsideRoot(self.file.clone()));
+        }
+        Ok(path)
+    }
+
+    pub fn open(&self, root: &Path) -> VersionResult<File> {
+        let path = self.path(root)?;
         match OpenOptions::new().read(true).open(&path) {
             Ok(file) => Ok(file),
             Err(e) => Err(VersionError::IoError(e)),
         }
     }
 
-    pub fn open_tmp(&self) -> VersionResult<File> {
-        let cd = match env::current_dir() {
-            Ok(cd) => cd,
-            Err(e) => return Err(VersionError::IoError(e)),
-        };
-        let mut path = cd.join(&self.file);
-        path = path.with_extension("tmp");
-        match OpenOptions::new().create(true).write(true).open(&path) {
+    pub fn open_tmp(&self, root: &Path) -> VersionResult<File> {
+        let path = self.path(root)?.with_extension("tmp");
+        match OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)
+        {
             Ok(file) => Ok(file),
             Err(e) => Err(VersionError::IoError(e)),
         }
     }
 
-    pub fn close(&self) -> VersionResult<()> {
-        let cd = match env::current_dir() {
-            Ok(cd) => cd,
-            Err(e) => return Err(VersionError::IoError(e)),
-        };
-        let old_path = cd.join(&self.file);
+    pub fn close(&self, root: &Path) -> VersionResult<()> {
+        let old_path = self.path(root)?;
         let new_path = old_path.with_extension("tmp");
         match remove_file(&old_path) {
             Ok(_) => (),
@@ -271,48 +646,177 @@ impl TrackedFiles {
         }
     }
 
-    pub fn read_lines(&self) -> VersionResult<Lines<BufReader<File>>> {
-        let file = self.open()?;
+    pub fn read_lines(&self, root: &Path) -> VersionResult<Lines<BufReader<File>>> {
+        let file = self.open(root)?;
         let reader = BufReader::new(file);
         Ok(reader.lines())
     }
 
-    pub fn writer(&self) -> VersionResult<BufWriter<File>> {
-        let file = self.open_tmp()?;
+    pub fn writer(&self, root: &Path) -> VersionResult<BufWriter<File>> {
+        let file = self.open_tmp(root)?;
         Ok(BufWriter::new(file))
     }
 
-    pub fn update(&self, version: String) -> VersionResult<()> {
-        let mut writer = self.writer()?;
+    /// Find the first line matching the expression, without modifying the file
+    pub fn find_match(&self, root: &Path) -> VersionResult<Option<FileMatch>> {
+        let regex = match Regex::new(&self.expr) {
+            Ok(re) => re,
+            Err(e) => return Err(VersionError::RegexError(e)),
+        };
+        for (idx, line) in self.read_lines(root)?.enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Err(VersionError::IoError(e)),
+            };
+            if let Some(value) = regex.captures(&line).and_then(|caps| caps.get(1)) {
+                return Ok(Some(FileMatch {
+                    line: idx + 1,
+                    value: value.as_str().to_string(),
+                    text: line,
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Find every line containing `version` as a standalone token
+    ///
+    /// Each candidate carries an expression anchored to the whole line, with the version
+    /// replaced by a capture group that matches any SemVer string.
+    pub fn suggest(&self, version: &str, root: &Path) -> VersionResult<Vec<Suggestion>> {
+        let mut suggestions = vec![];
+        for (idx, line) in self.read_lines(root)?.enumerate() {
+            let line = match line {
+                Ok(line) => line,
+                Err(e) => return Err(VersionError::IoError(e)),
+            };
+            if let Some(start) = find_version(&line, version) {
+                let prefix = &line[..start];
+                let suffix = &line[start + version.len()..];
+                let expr = format!(
+                    r"^\s*{}({}){}\s*$",
+                    regex::escape(prefix.trim_start()),
+                    SEMVER_EXPR,
+                    regex::escape(suffix.trim_end()),
+                );
+                suggestions.push(Suggestion {
+                    found: FileMatch {
+                        line: idx + 1,
+                        text: line.clone(),
+                        value: version.to_string(),
+                    },
+                    expr,
+                });
+            }
+        }
+        Ok(suggestions)
+    }
+
+    /// Find the first line carrying a marker comment
+    pub fn find_marker(&self, root: &Path) -> VersionResult<Option<FileMatch>> {
+        for (idx, line) in self.read_all(root)?.into_iter().enumerate() {
+            if markers::Marker::parse(&line).is_some() {
+                return Ok(Some(FileMatch {
+                    line: idx + 1,
+                    text: line,
+                    value: String::new(),
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Compare the value captured in the file against the expected version
+    pub fn check(
+        &self,
+        version: &Version,
+        packages: &PackageVersions,
+        root: &Path,
+    ) -> VersionResult<FileStatus> {
+        if self.markers {
+            let rendered =
+                markers::apply(&self.file, self.read_all(root)?, version, Some(packages))?;
+            return match rendered.drift {
+                _ if rendered.markers == 0 => Ok(FileStatus::NoMatch),
+                Some((found, expected)) => Ok(FileStatus::Drifted(found, expected)),
+                None => Ok(FileStatus::Synced),
+            };
+        }
+        match self.find_match(root)? {
+            Some(found) if found.value == version.to_string() => Ok(FileStatus::Synced),
+            Some(found) => Ok(FileStatus::Drifted(found, version.to_string())),
+            None => Ok(FileStatus::NoMatch),
+        }
+    }
+
+    fn read_all(&self, root: &Path) -> VersionResult<Vec<String>> {
+        let mut lines = vec![];
+        for line in self.read_lines(root)? {
+            match line {
+                Ok(line) => lines.push(line),
+                Err(e) => return Err(VersionError::IoError(e)),
+            }
+        }
+        Ok(lines)
+    }
+
+    fn write_all(&self, lines: Vec<String>, root: &Path) -> VersionResult<()> {
+        let mut writer = self.writer(root)?;
+        for line in lines {
+            match writer.write_all(format!("{}\n", line).as_bytes()) {
+                Ok(_) => (),
+                Err(e) => return Err(VersionError::IoError(e)),
+            };
+        }
+        match writer.flush() {
+            Ok(_) => (),
+            Err(e) => return Err(VersionError::IoError(e)),
+        };
+        drop(writer);
+        self.close(root)
+    }
+
+    /// Write `version` into the file
+    ///
+    /// `packages` is only consulted by `package=` markers; see [`markers::Marker::render`].
+    pub fn update(
+        &self,
+        version: &Version,
+        packages: Option<&PackageVersions>,
+        root: &Path,
+    ) -> VersionResult<()> {
+        if self.markers {
+            let rendered = markers::apply(&self.file, self.read_all(root)?, version, packages)?;
+            return self.write_all(rendered.lines, root);
+        }
+        let version = version.to_string();
+        let mut writer = self.writer(root)?;
         let regex = match Regex::new(&self.expr) {
             Ok(re) => re,
             Err(e) => return Err(VersionError::RegexError(e)),
         };
         let mut updated = false;
-        for line in match self.read_lines() {
-            Ok(lines) => lines,
-            Err(e) => return Err(e),
-        } {
+        for line in self.read_lines(root)? {
             let line = match line {
-                Ok(line) => format!("{}\n", line),
+                Ok(line) => line,
                 Err(e) => return Err(VersionError::IoError(e)),
             };
             if !updated {
                 if let Some(matches) = regex.captures(&line) {
-                    let new = line.replace(&matches[1], version.as_str());
-                    match writer.write(new.as_bytes()) {
+                    let new = format!("{}\n", line.replace(&matches[1], version.as_str()));
+                    match writer.write_all(new.as_bytes()) {
                         Ok(_) => (),
                         Err(e) => return Err(VersionError::IoError(e)),
                     };
                     updated = true;
                 } else {
-                    match writer.write(line.as_bytes()) {
+                    match writer.write_all(format!("{}\n", line).as_bytes()) {
                         Ok(_) => (),
                         Err(e) => return Err(VersionError::IoError(e)),
                     };
                 }
             } else {
-                match writer.write(line.as_bytes()) {
+                match writer.write_all(format!("{}\n", line).as_bytes()) {
                     Ok(_) => (),
                     Err(e) => return Err(VersionError::IoError(e)),
                 };
@@ -323,10 +827,127 @@ impl TrackedFiles {
             Err(e) => return Err(VersionError::IoError(e)),
         };
         drop(writer);
-        self.close()
+        self.close(root)
     }
 }
 
+/// Matches any SemVer version string
+pub const SEMVER_EXPR: &str = r"\d+\.\d+\.\d+(?:-[0-9A-Za-z.-]+)?(?:\+[0-9A-Za-z.-]+)?";
+
+/// Find the byte offset of `version` in `line` where it isn't part of a longer version string
+pub fn find_version(line: &str, version: &str) -> Option<usize> {
+    line.match_indices(version).map(|(idx, _)| idx).find(|&idx| {
+        let before = line[..idx].chars().next_back();
+        let after = line[idx + version.len()..].chars().next();
+        !matches!(before, Some(c) if c.is_ascii_digit() || c == '.')
+            && !matches!(after, Some(c) if c.is_ascii_alphanumeric() || matches!(c, '.' | '+' | '-'))
+    })
+}
+
+/// An unknown package error for `name`, suggesting the closest of `packages`
+fn unknown_package(name: &str, packages: &BTreeMap<String, Package>) -> VersionError {
+    let closest = packages
+        .keys()
+        .map(|known| (edit_distance(name, known), known))
+        .filter(|(distance, _)| *distance <= name.chars().count() / 3 + 1)
+        .min();
+    match closest {
+        Some((_, known)) => {
+            VersionError::UnknownPackage(format!("{} (did you mean {}?)", name, known))
+        }
+        None => VersionError::UnknownPackage(name.to_string()),
+    }
+}
+
+/// The number of single character edits needed to turn `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut prev: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut row = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = if ca == *cb { 0 } else { 1 };
+            row.push((prev[j] + cost).min(prev[j + 1] + 1).min(row[j] + 1));
+        }
+        prev = row;
+    }
+    prev[b.len()]
+}
+
+/// Lexically normalize a path, folding away `.` and `..` components
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                }
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => (),
+                _ => normalized.push(component),
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+/// The path from `base` to `path`, both absolute, climbing out of `base` with `..` if need be
+pub fn relative_path(path: &Path, base: &Path) -> PathBuf {
+    let (path, base) = (normalize(path), normalize(base));
+    let common = path
+        .components()
+        .zip(base.components())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let mut relative = PathBuf::new();
+    for _ in base.components().skip(common) {
+        relative.push(Component::ParentDir);
+    }
+    for component in path.components().skip(common) {
+        relative.push(component);
+    }
+    relative
+}
+
+/// Turn a path typed on the command line, relative to the working directory, into the form
+/// tracked files are stored in, relative to `root`
+///
+/// VERSION.toml may be in a parent directory, and package files are tracked relative to the
+/// package, so neither matches what was typed.
+pub fn root_relative(path: &str, root: &Path) -> VersionResult<String> {
+    let (path, root) = (std::path::absolute(path)?, std::path::absolute(root)?);
+    Ok(relative_path(&path, &root).to_string_lossy().to_string())
+}
+
+/// A line in a tracked file matched by its expression
+#[derive(Clone, Debug, PartialEq)]
+pub struct FileMatch {
+    /// The 1-based line number of the match
+    pub line: usize,
+    /// The full text of the matched line
+    pub text: String,
+    /// The value captured by the expression
+    pub value: String,
+}
+
+/// A candidate line for tracking, along with an expression that matches it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Suggestion {
+    pub found: FileMatch,
+    pub expr: String,
+}
+
+/// The state of a tracked file compared to the version it should contain
+#[derive(Clone, Debug, PartialEq)]
+pub enum FileStatus {
+    Synced,
+    /// The file holds a different value than expected, which is given alongside the match
+    Drifted(FileMatch, String),
+    NoMatch,
+}
+
 impl PartialEq<String> for TrackedFiles {
     fn eq(&self, other: &String) -> bool {
         self.file == *other
@@ -335,8 +956,7 @@ impl PartialEq<String> for TrackedFiles {
 
 impl PartialEq<PathBuf> for TrackedFiles {
     fn eq(&self, other: &PathBuf) -> bool {
-        let path = PathBuf::from(&self.file);
-        path == *other
+        normalize(Path::new(&self.file)) == normalize(other)
     }
 }
 
@@ -348,8 +968,7 @@ impl PartialEq<String> for &TrackedFiles {
 
 impl PartialEq<PathBuf> for &TrackedFiles {
     fn eq(&self, other: &PathBuf) -> bool {
-        let path = PathBuf::from(&self.file);
-        path == *other
+        normalize(Path::new(&self.file)) == normalize(other)
     }
 }
 
@@ -361,7 +980,107 @@ impl PartialEq<String> for &mut TrackedFiles {
 
 impl PartialEq<PathBuf> for &mut TrackedFiles {
     fn eq(&self, other: &PathBuf) -> bool {
-        let path = PathBuf::from(&self.file);
-        path == *other
+        normalize(Path::new(&self.file)) == normalize(other)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn finds_relative_paths() {
+        let root = Path::new("/project/crates/cli");
+        assert_eq!(
+            relative_path(Path::new("/project/crates/cli/Cargo.toml"), root),
+            PathBuf::from("Cargo.toml")
+        );
+        assert_eq!(
+            relative_path(Path::new("/project/README.md"), root),
+            PathBuf::from("../../README.md")
+        );
+    }
+
+    #[test]
+    fn resolves_relative_to_root() {
+        let file = TrackedFiles::new("sub/../Cargo.toml".to_string(), "(.*)".to_string());
+        let path = file.path(Path::new("/project")).unwrap();
+        assert_eq!(path, PathBuf::from("/project/Cargo.toml"));
+    }
+
+    #[test]
+    fn rejects_parent_escape() {
+        let file = TrackedFiles::new("../other/Cargo.toml".to_string(), "(.*)".to_string());
+        assert!(matches!(
+            file.path(Path::new("/project")),
+            Err(VersionError::OutsideRoot(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_absolute_path() {
+        let file = TrackedFiles::new("/project/Cargo.toml".to_string(), "(.*)".to_string());
+        assert!(matches!(
+            file.path(Path::new("/project")),
+            Err(VersionError::OutsideRoot(_))
+        ));
+    }
+
+    #[test]
+    fn allows_escape_when_opted_in() {
+        let mut file = TrackedFiles::new("../other/Cargo.toml".to_string(), "(.*)".to_string());
+        file.allow_outside_root = true;
+        let path = file.path(Path::new("/project")).unwrap();
+        assert_eq!(path, PathBuf::from("/other/Cargo.toml"));
+    }
+
+    #[test]
+    fn finds_standalone_versions() {
+        assert_eq!(find_version(r#"version = "1.2.3""#, "1.2.3"), Some(11));
+        assert_eq!(find_version("v1.2.3", "1.2.3"), Some(1));
+        assert_eq!(find_version("11.2.3", "1.2.3"), None);
+        assert_eq!(find_version("1.2.34 1.2.3-rc.1", "1.2.3"), None);
+    }
+
+    #[test]
+    fn suggested_expressions_match_other_versions() {
+        let prefix = r#"version = ""#;
+        let expr = format!(r#"^\s*{}({})"\s*$"#, regex::escape(prefix), SEMVER_EXPR);
+        let re = Regex::new(&expr).unwrap();
+        let caps = re.captures(r#"  version = "2.0.0-rc.1+build.5""#).unwrap();
+        assert_eq!(&caps[1], "2.0.0-rc.1+build.5");
+    }
+
+    #[test]
+    fn measures_edit_distance() {
+        assert_eq!(edit_distance("core", "core"), 0);
+        assert_eq!(edit_distance("cor", "core"), 1);
+        assert_eq!(edit_distance("sdk-js", "sdk-py"), 2);
+        assert_eq!(edit_distance("", "abc"), 3);
+    }
+
+    #[test]
+    fn renames_package_references() {
+        let mut ver = VersionFile::default();
+        ver.package.insert("core".to_string(), Package::default());
+        let app = Package {
+            depends_on: vec!["core".to_string()],
+            inherit: Some("core".to_string()),
+            ..Package::default()
+        };
+        ver.package.insert("app".to_string(), app);
+        ver.groups
+            .insert("all".to_string(), vec!["core".to_string()]);
+        ver.rename_package("core", "base").unwrap();
+        assert!(!ver.package.contains_key("core"));
+        assert_eq!(ver.package["app"].depends_on, vec!["base"]);
+        assert_eq!(ver.package["app"].inherit.as_deref(), Some("base"));
+        assert_eq!(ver.groups["all"], vec!["base"]);
+        match ver.get_package("bsae") {
+            Err(VersionError::UnknownPackage(msg)) => {
+                assert_eq!(msg, "bsae (did you mean base?)")
+            }
+            other => panic!("expected an unknown package, got {:?}", other),
+        }
     }
 }
@@ -0,0 +1,378 @@
+use crate::{
+    VersionError, VersionResult,
+    files::VersionFile,
+    version::{Bump, bump},
+};
+use semver::{BuildMetadata, Prerelease, Version};
+use serde::{Deserialize, Serialize};
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Run git in `dir`, returning its trimmed standard output
+pub fn git(dir: &Path, args: &[&str]) -> VersionResult<String> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        return Err(VersionError::GitError(format!(
+            "git {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// The `[git]` table in VERSION.toml
+///
+/// Templates can use `{version}`, and `{package}` for package tags. The commit message can also
+/// use `{tags}`, the release names of everything that changed, like `v1.2.0, cli@0.4.0`.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct GitConfig {
+    /// The template for root release tags, `v{version}` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    /// The template for package release tags, `{package}@{version}` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_tag: Option<String>,
+    /// The template for commit messages, `Release {tags}` if unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+}
+
+impl GitConfig {
+    pub fn is_empty(&self) -> bool {
+        self == &GitConfig::default()
+    }
+}
+
+/// What to do in git once a version change has been saved
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct GitOptions {
+    pub tag: bool,
+    pub allow_dirty: bool,
+}
+
+/// The tag template for the root or a package, with `{package}` filled in
+fn tag_template(ver: &VersionFile, package: Option<&str>) -> String {
+    let template = match package {
+        Some(name) => ver
+            .package
+            .get(name)
+            .and_then(|pkg| pkg.tag.as_deref())
+            .or(ver.git.package_tag.as_deref())
+            .unwrap_or("{package}@{version}"),
+        None => ver.git.tag.as_deref().unwrap_or("v{version}"),
+    };
+    template.replace("{package}", package.unwrap_or_default())
+}
+
+/// The release tag for the root version, `v1.2.3`, or a package, `name@1.2.3`, unless the
+/// templates in VERSION.toml say otherwise
+pub fn tag_name(ver: &VersionFile, package: Option<&str>, version: &Version) -> String {
+    tag_template(ver, package).replace("{version}", &version.to_string())
+}
+
+/// The version a release tag of the root or a package is for, `None` if it isn't one
+pub fn tag_version(ver: &VersionFile, package: Option<&str>, tag: &str) -> Option<Version> {
+    let template = tag_template(ver, package);
+    let (prefix, suffix) = template.split_once("{version}")?;
+    let version = tag.strip_prefix(prefix)?.strip_suffix(suffix)?;
+    Version::parse(version).ok()
+}
+
+pub fn tag_exists(dir: &Path, tag: &str) -> VersionResult<bool> {
+    let refname = format!("refs/tags/{}", tag);
+    match git(dir, &["rev-parse", "--quiet", "--verify", &refname]) {
+        Ok(_) => Ok(true),
+        Err(VersionError::GitError(_)) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// The glob release tags for the root or a package match
+pub fn tag_pattern(ver: &VersionFile, package: Option<&str>) -> String {
+    tag_template(ver, package).replace("{version}", "[0-9]*")
+}
+
+/// The nearest release tag reachable from `rev`, if there is one
+pub fn last_tag(
+    ver: &VersionFile,
+    package: Option<&str>,
+    rev: &str,
+) -> VersionResult<Option<String>> {
+    let pattern = tag_pattern(ver, package);
+    match git(
+        &ver.root,
+        &["describe", "--tags", "--abbrev=0", "--match", &pattern, rev],
+    ) {
+        Ok(tag) => Ok(Some(tag)),
+        Err(VersionError::GitError(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+/// The highest release tag of the root or a package, and the version it's for
+pub fn latest_release(
+    ver: &VersionFile,
+    package: Option<&str>,
+) -> VersionResult<Option<(String, Version)>> {
+    let pattern = tag_pattern(ver, package);
+    let tags = git(&ver.root, &["tag", "--list", &pattern])?;
+    Ok(tags
+        .lines()
+        .filter_map(|tag| Some((tag.to_string(), tag_version(ver, package, tag)?)))
+        .max_by(|(_, a), (_, b)| a.cmp(b)))
+}
+
+/// The tags pointing at `rev`
+pub fn tags_at(dir: &Path, rev: &str) -> VersionResult<Vec<String>> {
+    let tags = git(dir, &["tag", "--points-at", rev])?;
+    Ok(tags.lines().map(|tag| tag.to_string()).collect())
+}
+
+/// A development version for the root or a package, in the style of `git describe`
+///
+/// Builds after the last release tag get the next patch version, tagged with the number of
+/// commits since the release and the short commit hash, like `1.4.1-dev.7+g3f2a1bc`, with a
+/// `.dirty` marker for uncommitted changes. Without a release tag, the version in VERSION.toml
+/// and the full history are used. A clean build of a release tag is just its version.
+pub fn dev_version(ver: &VersionFile, package: Option<&str>) -> VersionResult<Version> {
+    let (base, distance) = match last_tag(ver, package, "HEAD")? {
+        Some(tag) => {
+            let version = tag_version(ver, package, &tag)
+                .ok_or_else(|| VersionError::GitError(format!("{} isn't a release tag", tag)))?;
+            (
+                version,
+                git(
+                    &ver.root,
+                    &["rev-list", "--count", &format!("{}..HEAD", tag)],
+                )?,
+            )
+        }
+        None => {
+            let version = match package {
+                Some(name) => ver.get_package(name)?.version.clone(),
+                None => ver.version.clone(),
+            };
+            (version, git(&ver.root, &["rev-list", "--count", "HEAD"])?)
+        }
+    };
+    let dirty = is_dirty(&ver.root)?;
+    if distance == "0" && !dirty {
+        return Ok(base);
+    }
+    let mut version = base;
+    bump(&mut version, Bump::Patch);
+    version.pre = Prerelease::new(&format!("dev.{}", distance))?;
+    let sha = git(&ver.root, &["rev-parse", "--short=7", "HEAD"])?;
+    let build = match dirty {
+        true => format!("g{}.dirty", sha),
+        false => format!("g{}", sha),
+    };
+    version.build = BuildMetadata::new(&build)?;
+    Ok(version)
+}
+
+/// VERSION.toml as it was at `rev`
+pub fn version_file_at(ver: &VersionFile, rev: &str) -> VersionResult<VersionFile> {
+    let contents = git(&ver.root, &["show", &format!("{}:./VERSION.toml", rev)])?;
+    VersionFile::parse(&contents, ver.root.clone())
+}
+
+/// Whether the working tree has uncommitted changes, ignoring untracked files
+pub fn is_dirty(dir: &Path) -> VersionResult<bool> {
+    Ok(!git(dir, &["status", "--porcelain", "--untracked-files=no"])?.is_empty())
+}
+
+/// Fail unless git knows who to commit as, so a commit can't fail after versions are saved
+pub fn check_identity(dir: &Path) -> VersionResult<()> {
+    git(dir, &["var", "GIT_AUTHOR_IDENT"])?;
+    git(dir, &["var", "GIT_COMMITTER_IDENT"])?;
+    Ok(())
+}
+
+/// Stage `paths`, including deletions, then commit just them, leaving anything else that
+/// was staged alone
+pub fn commit(dir: &Path, paths: &[PathBuf], message: &str) -> VersionResult<()> {
+    let paths: Vec<String> = paths.iter().map(|p| p.display().to_string()).collect();
+    let mut args = vec!["add", "--all", "--"];
+    args.extend(paths.iter().map(String::as_str));
+    git(dir, &args)?;
+    let mut args = vec!["commit", "--quiet", "--message", message, "--"];
+    args.extend(paths.iter().map(String::as_str));
+    git(dir, &args)?;
+    Ok(())
+}
+
+/// Create an annotated tag on HEAD
+pub fn tag(dir: &Path, name: &str) -> VersionResult<()> {
+    if tag_exists(dir, name)? {
+        return Err(VersionError::GitError(format!(
+            "tag {} already exists",
+            name
+        )));
+    }
+    git(dir, &["tag", "--annotate", name, "--message", name])?;
+    Ok(())
+}
+
+/// Files under `path`, relative to `dir`, that differ from `since`, including uncommitted and
+/// untracked files
+pub fn changed_files(dir: &Path, path: &str, since: &str) -> VersionResult<Vec<PathBuf>> {
+    let diff = git(
+        dir,
+        &["diff", "--name-only", "--relative", since, "--", path],
+    )?;
+    let untracked = git(
+        dir,
+        &["ls-files", "--others", "--exclude-standard", "--", path],
+    )?;
+    let mut files: Vec<PathBuf> = diff
+        .lines()
+        .chain(untracked.lines())
+        .filter(|line| !line.is_empty())
+        .map(|line| dir.join(line))
+        .collect();
+    files.sort();
+    files.dedup();
+    Ok(files)
+}
+
+/// A commit from `git log`
+#[derive(Clone, Debug, PartialEq)]
+pub struct Commit {
+    pub sha: String,
+    pub subject: String,
+}
+
+impl Commit {
+    pub fn short_sha(&self) -> &str {
+        &self.sha[..self.sha.len().min(7)]
+    }
+}
+
+/// The non-merge commits reachable from `to` but not `from`, newest first, limited to those
+/// touching `path` when one is given
+pub fn commits(
+    dir: &Path,
+    from: Option<&str>,
+    to: &str,
+    path: Option<&str>,
+) -> VersionResult<Vec<Commit>> {
+    let range = match from {
+        Some(from) => format!("{}..{}", from, to),
+        None => to.to_string(),
+    };
+    let mut args = vec!["log", "--no-merges", "--format=%H %s", &range];
+    if let Some(path) = path {
+        args.extend(["--", path]);
+    }
+    let log = git(dir, &args)?;
+    Ok(log
+        .lines()
+        .filter_map(|line| line.split_once(' '))
+        .map(|(sha, subject)| Commit {
+            sha: sha.to_string(),
+            subject: subject.to_string(),
+        })
+        .collect())
+}
+
+/// When `rev` was committed, in seconds since the Unix epoch
+pub fn commit_time(dir: &Path, rev: &str) -> VersionResult<u64> {
+    let time = git(dir, &["log", "-1", "--format=%ct", rev])?;
+    time.parse()
+        .map_err(|_| VersionError::GitError(format!("unexpected commit time '{}'", time)))
+}
+
+/// A package with changes since its last release
+#[derive(Clone, Debug, PartialEq)]
+pub struct ChangedPackage {
+    pub name: String,
+    /// The ref the package was compared against, `None` if it has never been released
+    pub since: Option<String>,
+    pub files: Vec<PathBuf>,
+}
+
+impl ChangedPackage {
+    pub fn describe(&self) -> String {
+        match self.since {
+            Some(ref since) => format!(
+                "{}: {} file(s) changed since {}",
+                self.name,
+                self.files.len(),
+                since
+            ),
+            None => format!("{}: never released", self.name),
+        }
+    }
+}
+
+/// Find the packages with files changed since `since`, or since each package's release tag
+///
+/// Only files under a package's `path` count, so packages without one change whenever anything
+/// in the project does. VERSION.toml is ignored, as every release rewrites it.
+pub fn changed_packages(
+    ver: &VersionFile,
+    since: Option<&str>,
+) -> VersionResult<Vec<ChangedPackage>> {
+    let version_file = ver.root.join("VERSION.toml");
+    let mut changed = vec![];
+    for (name, pkg) in ver.package.iter() {
+        let since = match since {
+            Some(since) => since.to_string(),
+            None => {
+                let tag = tag_name(ver, Some(name), &pkg.version);
+                if !tag_exists(&ver.root, &tag)? {
+                    changed.push(ChangedPackage {
+                        name: name.clone(),
+                        since: None,
+                        files: vec![],
+                    });
+                    continue;
+                }
+                tag
+            }
+        };
+        let path = pkg.path.as_deref().unwrap_or(".");
+        let files: Vec<PathBuf> = changed_files(&ver.root, path, &since)?
+            .into_iter()
+            .filter(|file| *file != version_file)
+            .collect();
+        if !files.is_empty() {
+            changed.push(ChangedPackage {
+                name: name.clone(),
+                since: Some(since),
+                files,
+            });
+        }
+    }
+    Ok(changed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn names_tags() {
+        let mut ver = VersionFile::default();
+        ver.package.insert("cli".to_string(), Default::default());
+        let version = Version::new(1, 2, 3);
+        assert_eq!(tag_name(&ver, None, &version), "v1.2.3");
+        assert_eq!(tag_name(&ver, Some("cli"), &version), "cli@1.2.3");
+        assert_eq!(
+            tag_version(&ver, Some("cli"), "cli@1.2.3"),
+            Some(version.clone())
+        );
+        assert_eq!(tag_version(&ver, None, "cli@1.2.3"), None);
+
+        ver.git.tag = Some("release-{version}".to_string());
+        ver.package.get_mut("cli").unwrap().tag = Some("{package}/v{version}".to_string());
+        assert_eq!(tag_name(&ver, None, &version), "release-1.2.3");
+        assert_eq!(tag_name(&ver, Some("cli"), &version), "cli/v1.2.3");
+        assert_eq!(tag_pattern(&ver, Some("cli")), "cli/v[0-9]*");
+        assert_eq!(tag_version(&ver, Some("cli"), "cli/v1.2.3"), Some(version));
+    }
+}
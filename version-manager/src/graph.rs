@@ -0,0 +1,453 @@
+use crate::{
+    VersionError, VersionResult,
+    files::{Package, VersionFile},
+    version::{Bump, bump},
+};
+use semver::Version;
+use std::collections::BTreeMap;
+
+/// A version change made to a package, and why it was made
+#[derive(Clone, Debug, PartialEq)]
+pub struct Change {
+    pub package: String,
+    pub from: Version,
+    pub to: Version,
+    /// Why the package changed, if it wasn't requested directly
+    pub cause: Option<Cause>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Cause {
+    /// A package this one depends on was bumped
+    Dependency(String),
+    /// Another member of this package's group changed
+    Group(String),
+    /// The root or package this one inherits its version from changed
+    Inherit(String),
+}
+
+impl Change {
+    pub fn describe(&self) -> String {
+        match self.cause {
+            Some(Cause::Dependency(ref dep)) => format!(
+                "{}: {} -> {} (depends on {})",
+                self.package, self.from, self.to, dep
+            ),
+            Some(Cause::Group(ref group)) => format!(
+                "{}: {} -> {} (group {})",
+                self.package, self.from, self.to, group
+            ),
+            Some(Cause::Inherit(ref source)) => format!(
+                "{}: {} -> {} (inherits {})",
+                self.package, self.from, self.to, source
+            ),
+            None => format!("{}: {} -> {}", self.package, self.from, self.to),
+        }
+    }
+}
+
+/// Carry a change to `package` from the version `from` over to the rest of its group, the
+/// packages depending on it, and the packages inheriting from any of them
+pub fn propagate(
+    ver: &mut VersionFile,
+    package: &str,
+    from: &Version,
+) -> VersionResult<Vec<Change>> {
+    if ver.get_package(package)?.version == *from {
+        return Ok(vec![]);
+    }
+    spread(ver, vec![(package.to_string(), from.clone())])
+}
+
+/// Carry a change to the root version from `from` over to the packages inheriting it, and on
+/// from them like any other package change
+pub fn propagate_root(ver: &mut VersionFile, from: &Version) -> VersionResult<Vec<Change>> {
+    if ver.version == *from {
+        return Ok(vec![]);
+    }
+    let mut changes = resolve_inherited(ver)?;
+    let moved = changes
+        .iter()
+        .map(|c| (c.package.clone(), c.from.clone()))
+        .collect();
+    changes.extend(spread(ver, moved)?);
+    Ok(changes)
+}
+
+/// Sync the groups of the `moved` packages, each given with its version before the change,
+/// cascade to their dependents and resolve inherited versions, then do the same for every
+/// inheriting package that moved as a result
+///
+/// A package is only spread from once, so packages inheriting from their own dependents
+/// can't loop.
+fn spread(ver: &mut VersionFile, mut moved: Vec<(String, Version)>) -> VersionResult<Vec<Change>> {
+    let mut changes = vec![];
+    let mut spread: Vec<String> = vec![];
+    while !moved.is_empty() {
+        let mut bumped = vec![];
+        for (name, from) in moved.iter() {
+            let to = ver.get_package(name)?.version.clone();
+            let synced = sync_group(ver, name)?;
+            if change_level(from, &to).is_some() {
+                bumped.push(name.clone());
+                bumped.extend(synced.iter().map(|c| c.package.clone()));
+            }
+            changes.extend(synced);
+            spread.push(name.clone());
+        }
+        changes.extend(cascade(ver, &bumped)?);
+        let inherited = resolve_inherited(ver)?;
+        moved = inherited
+            .iter()
+            .filter(|c| !spread.contains(&c.package))
+            .map(|c| (c.package.clone(), c.from.clone()))
+            .collect();
+        changes.extend(inherited);
+    }
+    Ok(changes)
+}
+
+/// Set the version of every package that inherits one, returning the changes made
+///
+/// `inherit = "root"` takes the root version, anything else names another package, which may
+/// itself inherit.
+pub fn resolve_inherited(ver: &mut VersionFile) -> VersionResult<Vec<Change>> {
+    let mut changes = vec![];
+    let names: Vec<String> = ver.package.keys().cloned().collect();
+    for name in names.iter() {
+        let mut chain = vec![name.clone()];
+        let mut source = ver.get_package(name)?.inherit.clone();
+        let version = loop {
+            match source {
+                None => break ver.get_package(chain.last().unwrap())?.version.clone(),
+                Some(ref inherit) if inherit == "root" => break ver.version.clone(),
+                Some(ref inherit) => {
+                    let pkg = match ver.package.get(inherit) {
+                        Some(pkg) => pkg,
+                        None => return Err(VersionError::UnknownPackage(inherit.clone())),
+                    };
+                    if chain.contains(inherit) {
+                        chain.push(inherit.clone());
+                        return Err(VersionError::DependencyCycle(chain.join(" -> ")));
+                    }
+                    chain.push(inherit.clone());
+                    source = pkg.inherit.clone();
+                }
+            }
+        };
+        let pkg = ver.get_package_mut(name)?;
+        if let Some(ref inherit) = pkg.inherit
+            && pkg.version != version
+        {
+            changes.push(Change {
+                package: name.clone(),
+                from: pkg.version.clone(),
+                to: version.clone(),
+                cause: Some(Cause::Inherit(inherit.clone())),
+            });
+            pkg.version = version;
+        }
+    }
+    Ok(changes)
+}
+
+/// Check that every group member exists and that no package is in more than one group
+pub fn validate_groups(ver: &VersionFile) -> VersionResult<()> {
+    let mut seen: BTreeMap<&String, &String> = BTreeMap::new();
+    for (group, members) in ver.groups.iter() {
+        for member in members.iter() {
+            if !ver.package.contains_key(member) {
+                return Err(VersionError::UnknownPackage(member.clone()));
+            }
+            if let Some(other) = seen.insert(member, group) {
+                return Err(VersionError::InvalidGroup(format!(
+                    "{} is in both {} and {}",
+                    member, other, group
+                )));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The name of the group `package` belongs to, if any
+pub fn group_of<'a>(ver: &'a VersionFile, package: &str) -> Option<&'a String> {
+    ver.groups
+        .iter()
+        .find(|(_, members)| members.iter().any(|m| m == package))
+        .map(|(group, _)| group)
+}
+
+/// Give every other member of `package`'s group the version `package` has, returning the
+/// changes made
+pub fn sync_group(ver: &mut VersionFile, package: &str) -> VersionResult<Vec<Change>> {
+    let group = match group_of(ver, package) {
+        Some(group) => group.clone(),
+        None => return Ok(vec![]),
+    };
+    let version = ver.get_package(package)?.version.clone();
+    let mut changes = vec![];
+    for member in ver.groups[&group].clone().iter() {
+        let pkg = ver.get_package_mut(member)?;
+        if member == package || pkg.inherit.is_some() || pkg.version == version {
+            continue;
+        }
+        changes.push(Change {
+            package: member.clone(),
+            from: pkg.version.clone(),
+            to: version.clone(),
+            cause: Some(Cause::Group(group.clone())),
+        });
+        pkg.version = version.clone();
+    }
+    Ok(changes)
+}
+
+/// Order packages so every package comes after the packages it depends on
+///
+/// Fails if a package depends on one that doesn't exist, or if the dependencies form a cycle.
+pub fn topo_order(packages: &BTreeMap<String, Package>) -> VersionResult<Vec<String>> {
+    for pkg in packages.values() {
+        for dep in pkg.depends_on.iter() {
+            if !packages.contains_key(dep) {
+                return Err(VersionError::UnknownPackage(dep.clone()));
+            }
+        }
+    }
+    let mut order = vec![];
+    let mut visiting = vec![];
+    for name in packages.keys() {
+        visit(name, packages, &mut visiting, &mut order)?;
+    }
+    Ok(order)
+}
+
+fn visit(
+    name: &String,
+    packages: &BTreeMap<String, Package>,
+    visiting: &mut Vec<String>,
+    order: &mut Vec<String>,
+) -> VersionResult<()> {
+    if order.contains(name) {
+        return Ok(());
+    }
+    if let Some(idx) = visiting.iter().position(|n| n == name) {
+        let mut cycle = visiting[idx..].to_vec();
+        cycle.push(name.clone());
+        return Err(VersionError::DependencyCycle(cycle.join(" -> ")));
+    }
+    visiting.push(name.clone());
+    for dep in packages[name].depends_on.iter() {
+        visit(dep, packages, visiting, order)?;
+    }
+    visiting.pop();
+    order.push(name.clone());
+    Ok(())
+}
+
+/// The level of the most significant version component that went up, if any
+pub fn change_level(from: &Version, to: &Version) -> Option<Bump> {
+    if to.major > from.major {
+        Some(Bump::Major)
+    } else if to.major == from.major && to.minor > from.minor {
+        Some(Bump::Minor)
+    } else if to.major == from.major && to.minor == from.minor && to.patch > from.patch {
+        Some(Bump::Patch)
+    } else {
+        None
+    }
+}
+
+/// Bump every package that depends, directly or transitively, on one of the `bumped` packages
+///
+/// Dependents are visited in dependency order and bumped by their `cascade` level, which
+/// defaults to a patch bump. A bumped dependent's group follows it, and since a group member
+/// can come before its dependents' dependencies in that order, the passes repeat until nothing
+/// else changes. Returns the changes made.
+pub fn cascade(ver: &mut VersionFile, bumped: &[String]) -> VersionResult<Vec<Change>> {
+    validate_groups(ver)?;
+    let order = topo_order(&ver.package)?;
+    let mut changed: Vec<String> = bumped.to_vec();
+    let mut changes = vec![];
+    loop {
+        let before = changed.len();
+        for name in order.iter() {
+            if changed.contains(name) {
+                continue;
+            }
+            let pkg = ver.get_package_mut(name)?;
+            // Inherited versions follow their source and are resolved separately
+            if pkg.inherit.is_some() {
+                continue;
+            }
+            let cause = match pkg.depends_on.iter().find(|dep| changed.contains(dep)) {
+                Some(cause) => cause.clone(),
+                None => continue,
+            };
+            let from = pkg.version.clone();
+            bump(&mut pkg.version, pkg.cascade.unwrap_or(Bump::Patch));
+            changes.push(Change {
+                package: name.clone(),
+                from,
+                to: pkg.version.clone(),
+                cause: Some(Cause::Dependency(cause)),
+            });
+            changed.push(name.clone());
+            for change in sync_group(ver, name)? {
+                changed.push(change.package.clone());
+                changes.push(change);
+            }
+        }
+        if changed.len() == before {
+            return Ok(changes);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn package(version: &str, depends_on: &[&str]) -> Package {
+        Package {
+            version: Version::parse(version).unwrap(),
+            depends_on: depends_on.iter().map(|d| d.to_string()).collect(),
+            ..Package::default()
+        }
+    }
+
+    #[test]
+    fn orders_dependencies_first() {
+        let mut packages = BTreeMap::new();
+        packages.insert("app".to_string(), package("1.0.0", &["sdk"]));
+        packages.insert("sdk".to_string(), package("1.0.0", &["core"]));
+        packages.insert("core".to_string(), package("1.0.0", &[]));
+        assert_eq!(topo_order(&packages).unwrap(), vec!["core", "sdk", "app"]);
+    }
+
+    #[test]
+    fn cascades_through_synced_group_members() {
+        let mut ver = VersionFile::default();
+        ver.package
+            .insert("core".to_string(), package("1.0.0", &[]));
+        ver.package
+            .insert("x".to_string(), package("1.0.0", &["core"]));
+        ver.package.insert("y".to_string(), package("1.0.0", &[]));
+        // Sorts before `y`, so it's passed over before the group brings `y` along
+        ver.package
+            .insert("a-z".to_string(), package("1.0.0", &["y"]));
+        ver.groups
+            .insert("sdk".to_string(), vec!["x".to_string(), "y".to_string()]);
+        ver.package.get_mut("core").unwrap().version = Version::new(1, 1, 0);
+        cascade(&mut ver, &["core".to_string()]).unwrap();
+        assert_eq!(ver.package["x"].version, Version::new(1, 0, 1));
+        assert_eq!(ver.package["y"].version, Version::new(1, 0, 1));
+        assert_eq!(ver.package["a-z"].version, Version::new(1, 0, 1));
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let mut packages = BTreeMap::new();
+        packages.insert("a".to_string(), package("1.0.0", &["b"]));
+        packages.insert("b".to_string(), package("1.0.0", &["a"]));
+        match topo_order(&packages) {
+            Err(VersionError::DependencyCycle(cycle)) => assert_eq!(cycle, "a -> b -> a"),
+            other => panic!("expected a cycle, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cascades_transitively() {
+        let mut ver = VersionFile::default();
+        ver.package
+            .insert("core".to_string(), package("1.1.0", &[]));
+        ver.package
+            .insert("sdk".to_string(), package("2.0.3", &["core"]));
+        let mut app = package("0.4.0", &["sdk"]);
+        app.cascade = Some(Bump::Minor);
+        ver.package.insert("app".to_string(), app);
+        let changes = cascade(&mut ver, &["core".to_string()]).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(ver.package["sdk"].version, Version::new(2, 0, 4));
+        assert_eq!(ver.package["app"].version, Version::new(0, 5, 0));
+    }
+
+    #[test]
+    fn spreads_from_inherited_versions() {
+        let mut ver = VersionFile::default();
+        let api = Package {
+            inherit: Some("root".to_string()),
+            ..package("1.0.0", &[])
+        };
+        ver.package.insert("api".to_string(), api);
+        ver.package
+            .insert("client".to_string(), package("0.1.0", &["api"]));
+        ver.package
+            .insert("docs".to_string(), package("1.0.0", &[]));
+        ver.groups.insert(
+            "site".to_string(),
+            vec!["api".to_string(), "docs".to_string()],
+        );
+        let from = std::mem::replace(&mut ver.version, Version::new(1, 1, 0));
+        propagate_root(&mut ver, &from).unwrap();
+        assert_eq!(ver.package["api"].version, Version::new(1, 1, 0));
+        assert_eq!(ver.package["client"].version, Version::new(0, 1, 1));
+        assert_eq!(ver.package["docs"].version, Version::new(1, 1, 0));
+    }
+
+    #[test]
+    fn syncs_groups() {
+        let mut ver = VersionFile::default();
+        ver.package
+            .insert("sdk-js".to_string(), package("1.2.0", &[]));
+        ver.package
+            .insert("sdk-py".to_string(), package("1.1.0", &[]));
+        ver.package
+            .insert("docs".to_string(), package("0.3.0", &["sdk-py"]));
+        ver.groups.insert(
+            "sdk".to_string(),
+            vec!["sdk-js".to_string(), "sdk-py".to_string()],
+        );
+        let changes = sync_group(&mut ver, "sdk-js").unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].cause, Some(Cause::Group("sdk".to_string())));
+        assert_eq!(ver.package["sdk-py"].version, Version::new(1, 2, 0));
+        let changes = cascade(&mut ver, &["sdk-js".to_string(), "sdk-py".to_string()]).unwrap();
+        assert_eq!(changes.len(), 1);
+        assert_eq!(ver.package["docs"].version, Version::new(0, 3, 1));
+    }
+
+    #[test]
+    fn rejects_overlapping_groups() {
+        let mut ver = VersionFile::default();
+        ver.package.insert("a".to_string(), package("1.0.0", &[]));
+        ver.groups.insert("one".to_string(), vec!["a".to_string()]);
+        ver.groups.insert("two".to_string(), vec!["a".to_string()]);
+        assert!(matches!(
+            validate_groups(&ver),
+            Err(VersionError::InvalidGroup(_))
+        ));
+    }
+
+    #[test]
+    fn resolves_inherited_versions() {
+        let mut ver = VersionFile {
+            version: Version::new(3, 1, 0),
+            ..VersionFile::default()
+        };
+        let mut api = package("0.0.0", &[]);
+        api.inherit = Some("root".to_string());
+        let mut client = package("0.0.0", &[]);
+        client.inherit = Some("api".to_string());
+        ver.package.insert("api".to_string(), api);
+        ver.package.insert("client".to_string(), client);
+        let changes = resolve_inherited(&mut ver).unwrap();
+        assert_eq!(changes.len(), 2);
+        assert_eq!(ver.package["client"].version, Version::new(3, 1, 0));
+        ver.get_package_mut("api").unwrap().inherit = Some("client".to_string());
+        assert!(matches!(
+            resolve_inherited(&mut ver),
+            Err(VersionError::DependencyCycle(_))
+        ));
+    }
+}
@@ -0,0 +1,282 @@
+use crate::{
+    VersionError, VersionResult, changelog::CONVENTIONAL_COMMIT, changes::ROOT, files::VersionFile,
+    git, prompt,
+};
+use regex::Regex;
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The `[hooks]` table in VERSION.toml, or `[package.NAME.hooks]` for a package
+///
+/// Each command runs through the shell with `VERSION_OLD`, `VERSION_NEW` and `VERSION_PACKAGE`
+/// set, in the project root or the package directory.
+#[derive(Deserialize, Serialize, Clone, Debug, Default, PartialEq, PartialOrd)]
+pub struct BumpHooks {
+    /// Commands run before the new version is saved, any failure leaving everything unchanged
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pre: Vec<String>,
+    /// Commands run once the new version and tracked files are saved
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub post: Vec<String>,
+}
+
+impl BumpHooks {
+    pub fn is_empty(&self) -> bool {
+        self.pre.is_empty() && self.post.is_empty()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stage {
+    Pre,
+    Post,
+}
+
+impl fmt::Display for Stage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Stage::Pre => write!(f, "pre"),
+            Stage::Post => write!(f, "post"),
+        }
+    }
+}
+
+/// The root or a package whose version changed, with the hooks to run for it
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bumped {
+    pub name: String,
+    pub old: Version,
+    pub new: Version,
+    pub dir: PathBuf,
+    pub hooks: BumpHooks,
+}
+
+/// Everything whose version differs between `before` and `after`, the root first
+///
+/// Packages added or removed in between have no old or new version, so they're left out.
+pub fn bumped(before: &VersionFile, after: &VersionFile) -> Vec<Bumped> {
+    let mut bumped = vec![];
+    if before.version != after.version {
+        bumped.push(Bumped {
+            name: ROOT.to_string(),
+            old: before.version.clone(),
+            new: after.version.clone(),
+            dir: after.root.clone(),
+            hooks: after.hooks.clone(),
+        });
+    }
+    for (name, pkg) in after.package.iter() {
+        if let Some(old) = before.package.get(name)
+            && old.version != pkg.version
+        {
+            bumped.push(Bumped {
+                name: name.clone(),
+                old: old.version.clone(),
+                new: pkg.version.clone(),
+                dir: pkg.root.clone(),
+                hooks: pkg.hooks.clone(),
+            });
+        }
+    }
+    bumped
+}
+
+/// Run the `stage` hooks for everything bumped, stopping at the first failure
+///
+/// Output is only shown when a command fails, on stderr ahead of the error.
+pub fn run_bump_hooks(bumped: &[Bumped], stage: Stage) -> VersionResult<()> {
+    for item in bumped.iter() {
+        let commands = match stage {
+            Stage::Pre => &item.hooks.pre,
+            Stage::Post => &item.hooks.post,
+        };
+        for command in commands.iter() {
+            let output = shell(command)
+                .current_dir(&item.dir)
+                .env("VERSION_OLD", item.old.to_string())
+                .env("VERSION_NEW", item.new.to_string())
+                .env("VERSION_PACKAGE", &item.name)
+                .output()?;
+            if !output.status.success() {
+                eprint!("{}", String::from_utf8_lossy(&output.stdout));
+                eprint!("{}", String::from_utf8_lossy(&output.stderr));
+                return Err(VersionError::HookFailed(
+                    format!("{} hook `{}` for {}", stage, command, item.name),
+                    output.status.to_string(),
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("sh");
+    cmd.arg("-c").arg(command);
+    cmd
+}
+
+#[cfg(windows)]
+fn shell(command: &str) -> Command {
+    let mut cmd = Command::new("cmd");
+    cmd.arg("/C").arg(command);
+    cmd
+}
+
+/// Marks git hooks this tool wrote, so they can be replaced and removed without asking
+const MARKER: &str = "# Installed by version-manager";
+
+/// The git hooks `install` knows about, and the command each runs
+const HOOKS: [(&str, &str); 2] = [
+    ("pre-commit", "version check"),
+    ("commit-msg", "version hooks check-message \"$1\""),
+];
+
+/// Where git looks for hooks, `.git/hooks` unless `core.hooksPath` says otherwise
+pub fn hooks_dir(root: &Path) -> VersionResult<PathBuf> {
+    let dir = git::git(root, &["rev-parse", "--git-path", "hooks"])?;
+    Ok(root.join(dir))
+}
+
+fn script(command: &str) -> String {
+    format!(
+        "#!/bin/sh\n{}, remove with `version hooks uninstall`\nexec {}\n",
+        MARKER, command
+    )
+}
+
+fn installed_by_us(path: &Path) -> VersionResult<bool> {
+    Ok(fs::read_to_string(path)?
+        .lines()
+        .any(|line| line.starts_with(MARKER)))
+}
+
+/// Write the pre-commit hook, and the commit-msg hook when `commit_msg` is set
+///
+/// Hooks written by something else are only replaced with `force`, or when the user agrees to
+/// it at a prompt.
+pub fn install(root: &Path, commit_msg: bool, force: bool) -> VersionResult<()> {
+    let dir = hooks_dir(root)?;
+    fs::create_dir_all(&dir)?;
+    for (name, command) in HOOKS {
+        if name == "commit-msg" && !commit_msg {
+            continue;
+        }
+        let path = dir.join(name);
+        if path.exists()
+            && !force
+            && !installed_by_us(&path)?
+            && !prompt::confirm(&format!("Replace the existing {} hook?", name))?
+        {
+            println!(
+                "Skipped {}, it already exists, pass --force to replace it",
+                name
+            );
+            continue;
+        }
+        fs::write(&path, script(command))?;
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        }
+        println!(
+            "Installed {}",
+            path.strip_prefix(root).unwrap_or(&path).display()
+        );
+    }
+    Ok(())
+}
+
+/// Remove the hooks `install` wrote, leaving any others alone
+pub fn uninstall(root: &Path) -> VersionResult<()> {
+    let dir = hooks_dir(root)?;
+    for (name, _) in HOOKS {
+        let path = dir.join(name);
+        if !path.exists() {
+            continue;
+        }
+        if installed_by_us(&path)? {
+            fs::remove_file(&path)?;
+            println!(
+                "Removed {}",
+                path.strip_prefix(root).unwrap_or(&path).display()
+            );
+        } else {
+            println!("Left {} alone, version-manager didn't install it", name);
+        }
+    }
+    Ok(())
+}
+
+/// Check that a commit message's subject follows Conventional Commits
+///
+/// Comment lines are ignored, as git strips them, and the subjects git writes for merges,
+/// reverts and fixups are let through.
+pub fn check_message(message: &str) -> VersionResult<()> {
+    let subject = message
+        .lines()
+        .map(str::trim_end)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .unwrap_or_default();
+    let generated = ["Merge ", "Revert \"", "fixup! ", "squash! ", "amend! "];
+    if generated.iter().any(|prefix| subject.starts_with(prefix)) {
+        return Ok(());
+    }
+    if !Regex::new(CONVENTIONAL_COMMIT)?.is_match(subject) {
+        return Err(VersionError::InvalidCommitMessage(subject.to_string()));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn checks_commit_messages() {
+        assert!(check_message("feat(cli)!: add hooks\n\nBody text").is_ok());
+        assert!(check_message("# Please enter the commit message\n\nfix: typo\n").is_ok());
+        assert!(check_message("Merge branch 'main' into feature").is_ok());
+        assert!(check_message("fixup! feat: add hooks").is_ok());
+        assert!(matches!(
+            check_message("Add hooks"),
+            Err(VersionError::InvalidCommitMessage(subject)) if subject == "Add hooks"
+        ));
+        assert!(check_message("feat:missing space").is_err());
+        assert!(check_message("# only comments\n").is_err());
+    }
+
+    #[test]
+    fn finds_bumped_versions() {
+        use crate::files::Package;
+
+        let mut before = VersionFile::default();
+        before.package.insert("cli".to_string(), Package::default());
+        before
+            .package
+            .insert("core".to_string(), Package::default());
+        let mut after = before.clone();
+        after.version = Version::new(0, 2, 0);
+        after.package.get_mut("core").unwrap().version = Version::new(0, 1, 1);
+        after.package.insert("docs".to_string(), Package::default());
+
+        let bumped: Vec<(String, String, String)> = bumped(&before, &after)
+            .into_iter()
+            .map(|b| (b.name, b.old.to_string(), b.new.to_string()))
+            .collect();
+        assert_eq!(
+            bumped,
+            [
+                ("root".to_string(), "0.1.0".to_string(), "0.2.0".to_string()),
+                ("core".to_string(), "0.1.0".to_string(), "0.1.1".to_string()),
+            ]
+        );
+    }
+}
@@ -1,5 +1,18 @@
+// The error tests predate clippy being run on test code and check lengths directly
+#![cfg_attr(test, allow(clippy::len_zero))]
+
+pub mod audit;
+pub mod changelog;
+pub mod changes;
 pub mod cli;
+pub mod diff;
 pub mod files;
+pub mod git;
+pub mod graph;
+pub mod hooks;
+pub mod markers;
+pub mod merge;
+pub mod prompt;
 pub mod run;
 pub mod version;
 
@@ -36,6 +49,50 @@ pub enum VersionError {
     InvalidVersion(#[from] semver::Error),
     #[error("Package name required")]
     PackageNameRequired,
+    #[error("Path is outside of the project root: {0}")]
+    OutsideRoot(String),
+    #[error("{0} tracked file(s) or package group(s) out of sync with VERSION.toml")]
+    Drift(usize),
+    #[error("File is not tracked: {0}")]
+    UntrackedFile(String),
+    #[error("Expression did not match anything in {0}")]
+    NoMatch(String),
+    #[error("Expression must have exactly one capture group: {0}")]
+    InvalidExpression(String),
+    #[error("File is already tracked: {0}")]
+    AlreadyTracked(String),
+    #[error("Several lines in {0} contain the version, choose one with --line")]
+    AmbiguousMatch(String),
+    #[error("Walk Error: {0}")]
+    WalkError(#[from] ignore::Error),
+    #[error("Invalid marker: {0}")]
+    InvalidMarker(String),
+    #[error("Unknown package: {0}")]
+    UnknownPackage(String),
+    #[error("Package dependencies form a cycle: {0}")]
+    DependencyCycle(String),
+    #[error("Invalid package group: {0}")]
+    InvalidGroup(String),
+    #[error("Package {0} inherits its version, change {1} instead")]
+    InheritedVersion(String, String),
+    #[error("Package already exists: {0}")]
+    PackageExists(String),
+    #[error("{0}")]
+    GitError(String),
+    #[error("Invalid changeset {0}")]
+    InvalidChangeset(String),
+    #[error("Invalid changelog: {0}")]
+    InvalidChangelog(String),
+    #[error("Tag {0} doesn't match version {1} in VERSION.toml")]
+    TagMismatch(String, semver::Version),
+    #[error("Unresolved merge conflicts: {0}")]
+    MergeConflict(String),
+    #[error("Commit message doesn't follow Conventional Commits: {0:?}")]
+    InvalidCommitMessage(String),
+    #[error("The {0} failed with {1}")]
+    HookFailed(String, String),
+    #[error("--commit only applies to commands that change a version")]
+    NothingToCommit,
 }
 
 pub type VersionResult<T> = Result<T, VersionError>;
@@ -46,6 +103,16 @@ impl VersionError {
     }
 
     pub fn terminate(&self, cmd: &mut Command) -> ! {
+        // Drift and failing hooks are the project's problem, not the command line's, so skip
+        // the usage that formatting against the command would add
+        if matches!(
+            self,
+            VersionError::Drift(_) | VersionError::HookFailed(_, _)
+        ) {
+            let err: error::Error = error::Error::raw(self.into(), format!("{}\n", self));
+            let _ = err.print();
+            std::process::exit(1);
+        }
         let err = self.cmd_error(cmd);
         err.exit()
     }
@@ -74,6 +141,28 @@ impl From<&VersionError> for error::ErrorKind {
             VersionError::PackageNameRequired => {
                 error::ErrorKind::DisplayHelpOnMissingArgumentOrSubcommand
             }
+            VersionError::OutsideRoot(_) => error::ErrorKind::ValueValidation,
+            VersionError::Drift(_) => error::ErrorKind::ValueValidation,
+            VersionError::UntrackedFile(_) => error::ErrorKind::InvalidValue,
+            VersionError::NoMatch(_) => error::ErrorKind::ValueValidation,
+            VersionError::InvalidExpression(_) => error::ErrorKind::ValueValidation,
+            VersionError::AlreadyTracked(_) => error::ErrorKind::ValueValidation,
+            VersionError::AmbiguousMatch(_) => error::ErrorKind::ValueValidation,
+            VersionError::WalkError(_) => error::ErrorKind::Io,
+            VersionError::InvalidMarker(_) => error::ErrorKind::ValueValidation,
+            VersionError::UnknownPackage(_) => error::ErrorKind::InvalidValue,
+            VersionError::DependencyCycle(_) => error::ErrorKind::ValueValidation,
+            VersionError::InvalidGroup(_) => error::ErrorKind::ValueValidation,
+            VersionError::InheritedVersion(_, _) => error::ErrorKind::ValueValidation,
+            VersionError::PackageExists(_) => error::ErrorKind::ValueValidation,
+            VersionError::GitError(_) => error::ErrorKind::Io,
+            VersionError::InvalidChangeset(_) => error::ErrorKind::ValueValidation,
+            VersionError::InvalidChangelog(_) => error::ErrorKind::ValueValidation,
+            VersionError::TagMismatch(_, _) => error::ErrorKind::ValueValidation,
+            VersionError::MergeConflict(_) => error::ErrorKind::ValueValidation,
+            VersionError::InvalidCommitMessage(_) => error::ErrorKind::ValueValidation,
+            VersionError::HookFailed(_, _) => error::ErrorKind::Io,
+            VersionError::NothingToCommit => error::ErrorKind::ArgumentConflict,
         }
     }
 }
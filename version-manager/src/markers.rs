@@ -0,0 +1,363 @@
+use crate::{
+    VersionError, VersionResult,
+    files::{FileMatch, PackageVersions, SEMVER_EXPR},
+};
+use regex::Regex;
+use semver::Version;
+
+/// The comment prefix that marks a line or block for replacement
+pub const MARKER: &str = "version-manager:";
+
+/// The part of the version a marker renders
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Component {
+    Version,
+    Major,
+    Minor,
+    Patch,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MarkerKind {
+    /// Replace the token before the marker on the same line
+    Line,
+    /// Replace every version in the lines up to the matching `end`
+    Start,
+    End,
+}
+
+/// A parsed `version-manager: ...` comment
+///
+/// Markers take the form `version-manager: [start|end] [version|major|minor|patch] [package=<name>]`,
+/// where every part is optional. Parsing stops at the first unknown word, so comment closers
+/// like `-->` or `*/` are ignored.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Marker {
+    pub kind: MarkerKind,
+    pub component: Component,
+    pub package: Option<String>,
+}
+
+impl Marker {
+    /// Find a marker in `line`, returning the byte offset it starts at
+    pub fn parse(line: &str) -> Option<(usize, Marker)> {
+        let start = line.find(MARKER)?;
+        let mut marker = Marker {
+            kind: MarkerKind::Line,
+            component: Component::Version,
+            package: None,
+        };
+        for word in line[start + MARKER.len()..].split_whitespace() {
+            match word {
+                "start" => marker.kind = MarkerKind::Start,
+                "end" => marker.kind = MarkerKind::End,
+                "version" => marker.component = Component::Version,
+                "major" => marker.component = Component::Major,
+                "minor" => marker.component = Component::Minor,
+                "patch" => marker.component = Component::Patch,
+                _ => match word.strip_prefix("package=") {
+                    Some(name) if !name.is_empty() => marker.package = Some(name.to_string()),
+                    _ => break,
+                },
+            }
+        }
+        Some((start, marker))
+    }
+
+    /// Render the value this marker stands for
+    ///
+    /// `packages` is `None` when only the scope's own version is known, in which case markers
+    /// for other packages render as `None` and are left untouched.
+    pub fn render(
+        &self,
+        version: &Version,
+        packages: Option<&PackageVersions>,
+    ) -> VersionResult<Option<String>> {
+        let version = match (&self.package, packages) {
+            (None, _) => version,
+            (Some(name), Some(packages)) => match packages.get(name) {
+                Some(version) => version,
+                None => return Err(VersionError::UnknownPackage(name.clone())),
+            },
+            (Some(_), None) => return Ok(None),
+        };
+        let value = match self.component {
+            Component::Version => version.to_string(),
+            Component::Major => version.major.to_string(),
+            Component::Minor => version.minor.to_string(),
+            Component::Patch => version.patch.to_string(),
+        };
+        Ok(Some(value))
+    }
+
+    fn pattern(&self) -> Regex {
+        match self.component {
+            Component::Version => Regex::new(SEMVER_EXPR),
+            _ => Regex::new(r"\b\d+\b"),
+        }
+        .expect("marker patterns are valid")
+    }
+}
+
+/// The result of applying the markers in a file
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rendered {
+    pub lines: Vec<String>,
+    /// The number of line markers and blocks found
+    pub markers: usize,
+    /// The first token that differed from its rendered value, and the value it should have
+    pub drift: Option<(FileMatch, String)>,
+}
+
+/// Replace the tokens selected by every marker in `lines`
+pub fn apply(
+    file: &str,
+    lines: Vec<String>,
+    version: &Version,
+    packages: Option<&PackageVersions>,
+) -> VersionResult<Rendered> {
+    let mut rendered = Rendered {
+        lines: Vec::with_capacity(lines.len()),
+        markers: 0,
+        drift: None,
+    };
+    let mut block: Option<(Marker, Option<String>)> = None;
+    for (idx, line) in lines.into_iter().enumerate() {
+        let parsed = Marker::parse(&line);
+        match (parsed, block.is_some()) {
+            (Some((_, marker)), true) if marker.kind == MarkerKind::End => {
+                block = None;
+                rendered.lines.push(line);
+            }
+            (Some((_, marker)), false) if marker.kind == MarkerKind::End => {
+                return Err(VersionError::InvalidMarker(format!(
+                    "{}:{}: end without start",
+                    file,
+                    idx + 1
+                )));
+            }
+            (Some((_, marker)), true) if marker.kind == MarkerKind::Start => {
+                return Err(VersionError::InvalidMarker(format!(
+                    "{}:{}: nested start",
+                    file,
+                    idx + 1
+                )));
+            }
+            (Some((_, marker)), false) if marker.kind == MarkerKind::Start => {
+                rendered.markers += 1;
+                let value = marker.render(version, packages)?;
+                block = Some((marker, value));
+                rendered.lines.push(line);
+            }
+            (Some((start, marker)), false) => {
+                rendered.markers += 1;
+                let (head, tail) = line.split_at(start);
+                let head = match marker.render(version, packages)? {
+                    Some(value) => {
+                        replace_last(&marker.pattern(), head, &value, idx, &line, &mut rendered)
+                    }
+                    None => head.to_string(),
+                };
+                rendered.lines.push(format!("{}{}", head, tail));
+            }
+            (_, true) => {
+                let new = match &block {
+                    Some((marker, Some(value))) => {
+                        replace_all(&marker.pattern(), &line, value, idx, &mut rendered)
+                    }
+                    _ => line,
+                };
+                rendered.lines.push(new);
+            }
+            (None, false) => rendered.lines.push(line),
+        }
+    }
+    if block.is_some() {
+        return Err(VersionError::InvalidMarker(format!(
+            "{}: start without end",
+            file
+        )));
+    }
+    Ok(rendered)
+}
+
+/// Find the first whole version a marker points at, for importing it
+///
+/// Only `version` markers for the scope's own version count, as `major`, `minor` and `patch`
+/// markers and markers for other packages don't say what the version is.
+pub fn find_version(lines: &[String]) -> Option<FileMatch> {
+    let pattern = Regex::new(SEMVER_EXPR).expect("marker patterns are valid");
+    let found = |idx: usize, line: &str, value: &str| FileMatch {
+        line: idx + 1,
+        text: line.to_string(),
+        value: value.to_string(),
+    };
+    let own_version =
+        |marker: &Marker| marker.component == Component::Version && marker.package.is_none();
+    let mut in_block: Option<bool> = None;
+    for (idx, line) in lines.iter().enumerate() {
+        match (Marker::parse(line), in_block) {
+            (Some((_, marker)), _) if marker.kind == MarkerKind::End => in_block = None,
+            (Some((_, marker)), _) if marker.kind == MarkerKind::Start => {
+                in_block = Some(own_version(&marker));
+            }
+            (Some((start, marker)), _) if own_version(&marker) => {
+                if let Some(value) = pattern.find_iter(&line[..start]).last() {
+                    return Some(found(idx, line, value.as_str()));
+                }
+            }
+            (None, Some(true)) => {
+                if let Some(value) = pattern.find(line) {
+                    return Some(found(idx, line, value.as_str()));
+                }
+            }
+            _ => (),
+        }
+    }
+    None
+}
+
+fn record_drift(rendered: &mut Rendered, idx: usize, line: &str, old: &str, new: &str) {
+    if old != new && rendered.drift.is_none() {
+        let found = FileMatch {
+            line: idx + 1,
+            text: line.to_string(),
+            value: old.to_string(),
+        };
+        rendered.drift = Some((found, new.to_string()));
+    }
+}
+
+fn replace_last(
+    pattern: &Regex,
+    head: &str,
+    value: &str,
+    idx: usize,
+    line: &str,
+    rendered: &mut Rendered,
+) -> String {
+    match pattern.find_iter(head).last() {
+        Some(found) => {
+            record_drift(rendered, idx, line, found.as_str(), value);
+            format!(
+                "{}{}{}",
+                &head[..found.start()],
+                value,
+                &head[found.end()..]
+            )
+        }
+        None => head.to_string(),
+    }
+}
+
+fn replace_all(
+    pattern: &Regex,
+    line: &str,
+    value: &str,
+    idx: usize,
+    rendered: &mut Rendered,
+) -> String {
+    for found in pattern.find_iter(line) {
+        record_drift(rendered, idx, line, found.as_str(), value);
+    }
+    pattern.replace_all(line, value).to_string()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn finds_marked_versions() {
+        let text = lines(
+            "MAJOR = 1 # version-manager: major\n\
+             cli = \"0.1.0\" # version-manager: package=cli\n\
+             <!-- version-manager: start -->\n\
+             Install 1.4.2 or later\n\
+             <!-- version-manager: end -->",
+        );
+        let found = find_version(&text).unwrap();
+        assert_eq!((found.line, found.value.as_str()), (4, "1.4.2"));
+        assert!(find_version(&text[..2]).is_none());
+    }
+
+    #[test]
+    fn parses_markers() {
+        let (start, marker) = Marker::parse("VERSION=1.0.0 # version-manager: major").unwrap();
+        assert_eq!(start, 16);
+        assert_eq!(marker.kind, MarkerKind::Line);
+        assert_eq!(marker.component, Component::Major);
+        let (_, marker) = Marker::parse("<!-- version-manager: start package=cli -->").unwrap();
+        assert_eq!(marker.kind, MarkerKind::Start);
+        assert_eq!(marker.package.as_deref(), Some("cli"));
+        assert!(Marker::parse("version = \"1.0.0\"").is_none());
+    }
+
+    #[test]
+    fn replaces_line_markers() {
+        let version = Version::new(2, 3, 4);
+        let mut packages = PackageVersions::new();
+        packages.insert("cli".to_string(), Version::new(0, 9, 0));
+        let text = lines(
+            "v = \"1.0.0\" # version-manager: version\n\
+             MAJOR = 1 // version-manager: major\n\
+             cli = \"0.1.0\" # version-manager: package=cli",
+        );
+        let rendered = apply("test", text, &version, Some(&packages)).unwrap();
+        assert_eq!(rendered.markers, 3);
+        assert_eq!(
+            rendered.lines[0],
+            "v = \"2.3.4\" # version-manager: version"
+        );
+        assert_eq!(rendered.lines[1], "MAJOR = 2 // version-manager: major");
+        assert_eq!(
+            rendered.lines[2],
+            "cli = \"0.9.0\" # version-manager: package=cli"
+        );
+        let (found, expected) = rendered.drift.unwrap();
+        assert_eq!(found.value, "1.0.0");
+        assert_eq!(expected, "2.3.4");
+    }
+
+    #[test]
+    fn replaces_blocks() {
+        let version = Version::new(1, 1, 0);
+        let text = lines(
+            "<!-- version-manager: start -->\n\
+             cargo install version@1.0.0\n\
+             curl .../v1.0.0/version-v1.0.0.tar.gz\n\
+             <!-- version-manager: end -->\n\
+             Released 1.0.0",
+        );
+        let rendered = apply("test", text, &version, None).unwrap();
+        assert_eq!(rendered.lines[1], "cargo install version@1.1.0");
+        assert_eq!(rendered.lines[2], "curl .../v1.1.0/version-v1.1.0.tar.gz");
+        assert_eq!(rendered.lines[4], "Released 1.0.0");
+    }
+
+    #[test]
+    fn rejects_unterminated_blocks() {
+        let version = Version::new(1, 1, 0);
+        let text = lines("# version-manager: start\n1.0.0");
+        assert!(matches!(
+            apply("test", text, &version, None),
+            Err(VersionError::InvalidMarker(_))
+        ));
+    }
+
+    #[test]
+    fn skips_unknown_packages_without_context() {
+        let version = Version::new(1, 1, 0);
+        let text = lines("1.0.0 # version-manager: package=cli");
+        let rendered = apply("test", text.clone(), &version, None).unwrap();
+        assert_eq!(rendered.lines, text);
+        assert!(matches!(
+            apply("test", text, &version, Some(&PackageVersions::new())),
+            Err(VersionError::UnknownPackage(_))
+        ));
+    }
+}
@@ -0,0 +1,345 @@
+use crate::{
+    VersionError, VersionResult,
+    files::{Package, TrackedFiles, VersionFile},
+};
+use semver::Version;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::Path,
+};
+
+/// Merge VERSION.toml three ways, as git does with `%O %A %B`, writing the result over `ours`
+///
+/// Versions take the higher of the two sides, and tracked files, dependencies and group
+/// members are merged as sets. Anything else only conflicts when both sides changed it to
+/// different values, in which case `ours` is left alone for git to report.
+pub fn merge_files(base: &Path, ours: &Path, theirs: &Path) -> VersionResult<()> {
+    let base = match fs::read_to_string(base)? {
+        // A file added on both sides has an empty base
+        text if text.trim().is_empty() => None,
+        text => Some(toml::from_str::<VersionFile>(&text)?),
+    };
+    let ours_ver: VersionFile = toml::from_str(&fs::read_to_string(ours)?)?;
+    let theirs_ver: VersionFile = toml::from_str(&fs::read_to_string(theirs)?)?;
+    let merged = merge(base.as_ref(), &ours_ver, &theirs_ver)?;
+    fs::write(ours, toml::to_string_pretty(&merged)?)?;
+    Ok(())
+}
+
+/// Merge two sides of VERSION.toml against their common base
+pub fn merge(
+    base: Option<&VersionFile>,
+    ours: &VersionFile,
+    theirs: &VersionFile,
+) -> VersionResult<VersionFile> {
+    let mut conflicts = vec![];
+    let mut merged = ours.clone();
+    merged.version = merge_version(base.map(|b| &b.version), &ours.version, &theirs.version);
+    merged.files = merge_files_list(
+        "root",
+        base.map(|b| b.files.as_slice()),
+        &ours.files,
+        &theirs.files,
+        &mut conflicts,
+    );
+    merged.changelog = merge_value(
+        "the changelog settings",
+        base.map(|b| &b.changelog),
+        &ours.changelog,
+        &theirs.changelog,
+        &mut conflicts,
+    );
+    merged.git = merge_value(
+        "the git settings",
+        base.map(|b| &b.git),
+        &ours.git,
+        &theirs.git,
+        &mut conflicts,
+    );
+    merged.hooks = merge_value(
+        "the hooks",
+        base.map(|b| &b.hooks),
+        &ours.hooks,
+        &theirs.hooks,
+        &mut conflicts,
+    );
+
+    let groups: BTreeSet<&String> = ours.groups.keys().chain(theirs.groups.keys()).collect();
+    merged.groups = BTreeMap::new();
+    for name in groups {
+        let base_group = base.map(|b| b.groups.get(name));
+        let members = match (ours.groups.get(name), theirs.groups.get(name)) {
+            (Some(o), Some(t)) => Some(merge_set(base_group.flatten().map(Vec::as_slice), o, t)),
+            (o, t) => merge_value(
+                &format!("group {}", name),
+                base_group.as_ref(),
+                &o,
+                &t,
+                &mut conflicts,
+            )
+            .cloned(),
+        };
+        if let Some(members) = members {
+            merged.groups.insert(name.clone(), members);
+        }
+    }
+
+    let names: BTreeSet<&String> = ours.package.keys().chain(theirs.package.keys()).collect();
+    merged.package = BTreeMap::new();
+    for name in names {
+        let base_pkg = base.map(|b| b.package.get(name));
+        let pkg = match (ours.package.get(name), theirs.package.get(name)) {
+            (Some(o), Some(t)) => Some(merge_package(
+                name,
+                base_pkg.flatten(),
+                o,
+                t,
+                &mut conflicts,
+            )),
+            // Added on one side, or removed on one side and untouched on the other
+            (o, t) => merge_value(
+                &format!("package {}", name),
+                base_pkg.as_ref(),
+                &o,
+                &t,
+                &mut conflicts,
+            )
+            .cloned(),
+        };
+        if let Some(pkg) = pkg {
+            merged.package.insert(name.clone(), pkg);
+        }
+    }
+
+    if !conflicts.is_empty() {
+        return Err(VersionError::MergeConflict(conflicts.join("; ")));
+    }
+    Ok(merged)
+}
+
+fn merge_package(
+    name: &str,
+    base: Option<&Package>,
+    ours: &Package,
+    theirs: &Package,
+    conflicts: &mut Vec<String>,
+) -> Package {
+    let field = |field: &str| format!("{} of package {}", field, name);
+    Package {
+        version: merge_version(base.map(|b| &b.version), &ours.version, &theirs.version),
+        inherit: merge_value(
+            &field("the inherited version"),
+            base.map(|b| &b.inherit),
+            &ours.inherit,
+            &theirs.inherit,
+            conflicts,
+        ),
+        path: merge_value(
+            &field("the path"),
+            base.map(|b| &b.path),
+            &ours.path,
+            &theirs.path,
+            conflicts,
+        ),
+        changelog: merge_value(
+            &field("the changelog"),
+            base.map(|b| &b.changelog),
+            &ours.changelog,
+            &theirs.changelog,
+            conflicts,
+        ),
+        tag: merge_value(
+            &field("the tag template"),
+            base.map(|b| &b.tag),
+            &ours.tag,
+            &theirs.tag,
+            conflicts,
+        ),
+        files: merge_files_list(
+            name,
+            base.map(|b| b.files.as_slice()),
+            &ours.files,
+            &theirs.files,
+            conflicts,
+        ),
+        depends_on: merge_set(
+            base.map(|b| b.depends_on.as_slice()),
+            &ours.depends_on,
+            &theirs.depends_on,
+        ),
+        cascade: merge_value(
+            &field("the cascade level"),
+            base.map(|b| &b.cascade),
+            &ours.cascade,
+            &theirs.cascade,
+            conflicts,
+        ),
+        hooks: merge_value(
+            &field("the hooks"),
+            base.map(|b| &b.hooks),
+            &ours.hooks,
+            &theirs.hooks,
+            conflicts,
+        ),
+        root: ours.root.clone(),
+    }
+}
+
+/// The side that changed, or the higher version when both did
+fn merge_version(base: Option<&Version>, ours: &Version, theirs: &Version) -> Version {
+    match base {
+        Some(base) if ours == base => theirs.clone(),
+        Some(base) if theirs == base => ours.clone(),
+        _ => ours.max(theirs).clone(),
+    }
+}
+
+/// The side that changed, recording a conflict when both changed it differently
+fn merge_value<T: PartialEq + Clone>(
+    what: &str,
+    base: Option<&T>,
+    ours: &T,
+    theirs: &T,
+    conflicts: &mut Vec<String>,
+) -> T {
+    match base {
+        _ if ours == theirs => ours.clone(),
+        Some(base) if ours == base => theirs.clone(),
+        Some(base) if theirs == base => ours.clone(),
+        _ => {
+            conflicts.push(format!("both sides changed {}", what));
+            ours.clone()
+        }
+    }
+}
+
+/// What both sides kept, plus what either added, in the order first seen
+fn merge_set(base: Option<&[String]>, ours: &[String], theirs: &[String]) -> Vec<String> {
+    let base = base.unwrap_or_default();
+    let mut merged: Vec<String> = vec![];
+    for item in ours.iter().chain(theirs.iter()) {
+        let kept = ours.contains(item) && theirs.contains(item);
+        if (kept || !base.contains(item)) && !merged.contains(item) {
+            merged.push(item.clone());
+        }
+    }
+    merged
+}
+
+/// Tracked files merged by path, each added, removed or changed on either side
+fn merge_files_list(
+    owner: &str,
+    base: Option<&[TrackedFiles]>,
+    ours: &[TrackedFiles],
+    theirs: &[TrackedFiles],
+    conflicts: &mut Vec<String>,
+) -> Vec<TrackedFiles> {
+    let find = |files: &[TrackedFiles], path: &str| files.iter().find(|f| f.file == path).cloned();
+    let mut paths: Vec<&str> = vec![];
+    for file in ours.iter().chain(theirs.iter()) {
+        if !paths.contains(&file.file.as_str()) {
+            paths.push(&file.file);
+        }
+    }
+    let mut merged = vec![];
+    for path in paths {
+        let base_file = base.map(|files| find(files, path));
+        let file = merge_value(
+            &format!("how {} tracks {}", owner, path),
+            base_file.as_ref(),
+            &find(ours, path),
+            &find(theirs, path),
+            conflicts,
+        );
+        merged.extend(file);
+    }
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse(text: &str) -> VersionFile {
+        toml::from_str(text).unwrap()
+    }
+
+    const BASE: &str = r#"
+version = "1.0.0"
+files = [{ file = "Cargo.toml", expr = "(.*)" }]
+
+[package.cli]
+version = "0.3.0"
+files = []
+
+[package.core]
+version = "1.1.0"
+files = []
+"#;
+
+    #[test]
+    fn takes_the_higher_versions() {
+        let ours = parse(&BASE.replace("0.3.0", "0.4.0"));
+        let theirs = parse(&BASE.replace("0.3.0", "0.3.1").replace("1.1.0", "1.2.0"));
+        let merged = merge(Some(&parse(BASE)), &ours, &theirs).unwrap();
+        assert_eq!(merged.package["cli"].version, Version::new(0, 4, 0));
+        assert_eq!(merged.package["core"].version, Version::new(1, 2, 0));
+        assert_eq!(merged.version, Version::new(1, 0, 0));
+    }
+
+    #[test]
+    fn unions_tracked_files_and_packages() {
+        let base = parse(BASE);
+        let mut ours = base.clone();
+        ours.files.push(TrackedFiles::new(
+            "README.md".to_string(),
+            "(.*)".to_string(),
+        ));
+        ours.package.remove("core");
+        let mut theirs = base.clone();
+        theirs.files.push(TrackedFiles::new(
+            "install.sh".to_string(),
+            "(.*)".to_string(),
+        ));
+        theirs
+            .package
+            .insert("docs".to_string(), Package::default());
+        let merged = merge(Some(&base), &ours, &theirs).unwrap();
+        let files: Vec<&str> = merged.files.iter().map(|f| f.file.as_str()).collect();
+        assert_eq!(files, ["Cargo.toml", "README.md", "install.sh"]);
+        let names: Vec<&String> = merged.package.keys().collect();
+        assert_eq!(names, ["cli", "docs"]);
+    }
+
+    #[test]
+    fn reports_real_conflicts() {
+        let base = parse(BASE);
+        let mut ours = base.clone();
+        ours.files[0].expr = "version = (.*)".to_string();
+        ours.package.get_mut("cli").unwrap().path = Some("cli".to_string());
+        let mut theirs = base.clone();
+        theirs.files[0].expr = "v(.*)".to_string();
+        theirs.package.get_mut("cli").unwrap().path = Some("crates/cli".to_string());
+        match merge(Some(&base), &ours, &theirs) {
+            Err(VersionError::MergeConflict(conflicts)) => assert_eq!(
+                conflicts,
+                "both sides changed how root tracks Cargo.toml; \
+                 both sides changed the path of package cli"
+            ),
+            other => panic!("expected a conflict, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn conflicts_when_a_removed_package_changed() {
+        let base = parse(BASE);
+        let mut ours = base.clone();
+        ours.package.remove("cli");
+        let theirs = parse(&BASE.replace("0.3.0", "0.3.1"));
+        assert!(matches!(
+            merge(Some(&base), &ours, &theirs),
+            Err(VersionError::MergeConflict(_))
+        ));
+    }
+}
@@ -0,0 +1,50 @@
+use crate::{VersionError, VersionResult};
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Whether stdin is attached to a terminal that can answer prompts
+pub fn interactive() -> bool {
+    io::stdin().is_terminal()
+}
+
+fn read_answer(question: &str) -> VersionResult<String> {
+    print!("{} ", question);
+    if let Err(e) = io::stdout().flush() {
+        return Err(VersionError::IoError(e));
+    }
+    let mut answer = String::new();
+    match io::stdin().lock().read_line(&mut answer) {
+        Ok(_) => Ok(answer.trim().to_string()),
+        Err(e) => Err(VersionError::IoError(e)),
+    }
+}
+
+/// Ask the user to pick one of `count` numbered options
+///
+/// Returns the 0-based index of the choice, or `None` when stdin is not a terminal or the
+/// answer is empty.
+pub fn choose(question: &str, count: usize) -> VersionResult<Option<usize>> {
+    if !interactive() {
+        return Ok(None);
+    }
+    loop {
+        let answer = read_answer(&format!("{} [1-{}]:", question, count))?;
+        if answer.is_empty() {
+            return Ok(None);
+        }
+        match answer.parse::<usize>() {
+            Ok(choice) if (1..=count).contains(&choice) => return Ok(Some(choice - 1)),
+            _ => println!("Please enter a number between 1 and {}", count),
+        }
+    }
+}
+
+/// Ask the user a yes/no question, defaulting to no
+///
+/// Always answers no when stdin is not a terminal.
+pub fn confirm(question: &str) -> VersionResult<bool> {
+    if !interactive() {
+        return Ok(false);
+    }
+    let answer = read_answer(&format!("{} [y/N]:", question))?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
@@ -1,24 +1,374 @@
 use crate::{
-    VersionError, VersionResult,
-    files::{ModifyTrackedFiles, Package, VersionFile},
-    version::{Operator, PrereleaseWrapper, Scope, SetTypes},
+    VersionError, VersionResult, audit, changelog, changes, diff,
+    files::{
+        FileStatus, ModifyTrackedFiles, Package, PackageVersions, Suggestion, TrackedFiles,
+        VersionFile, root_relative,
+    },
+    git::{self, GitOptions},
+    graph::{self, Change},
+    hooks::{self, Stage},
+    merge, prompt,
+    version::{
+        Bump, ChangelogOp, HooksOp, Operator, PrereleaseWrapper, Scope, Selector, SetTypes, bump,
+    },
 };
 use semver::{BuildMetadata, Prerelease, Version};
 use serde_json::json;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 pub fn run(scope: Scope, file_path: PathBuf) -> VersionResult<()> {
-    let file = VersionFile::load(file_path.clone())?;
+    if let Scope::Commit(options, scope) = scope {
+        return commit(*scope, file_path, &options);
+    }
+    // Merges work on git's temporary files, not the project's VERSION.toml
+    if let Scope::MergeDriver(ref base, ref ours, ref theirs) = scope {
+        return merge::merge_files(base, ours, theirs);
+    }
+    // The commit-msg hook only needs the message
+    if let Scope::Hooks(HooksOp::CheckMessage(ref path)) = scope {
+        return hooks::check_message(&std::fs::read_to_string(path)?);
+    }
+    let mut file = VersionFile::load(file_path.clone())?;
+    if let Scope::Packages(ref selector, ref pkg_scope) = scope
+        && is_package_only(pkg_scope)
+    {
+        return run_selected(&file, selector, pkg_scope, file_path);
+    }
+    let scope = match scope {
+        Scope::Package(name, pkg_scope) if name.is_empty() && !pkg_scope.is_multi_package() => {
+            let cwd = std::env::current_dir()?;
+            match file.package_at(&cwd) {
+                Some(name) => Scope::Package(name, pkg_scope),
+                None => return Err(VersionError::PackageNameRequired),
+            }
+        }
+        scope => scope,
+    };
+    let scope = match scope {
+        Scope::SyncFromGit => Scope::Set(sync_from_git(&file, None)?),
+        Scope::Package(name, pkg_scope) if *pkg_scope == Scope::SyncFromGit => {
+            let set = Scope::Set(sync_from_git(&file, Some(&name))?);
+            Scope::Package(name, Box::new(set))
+        }
+        scope => scope,
+    };
+    match scope {
+        Scope::Check => return check(&file),
+        Scope::VerifyTag => return verify_tag(&file, None),
+        Scope::Hooks(HooksOp::Install(commit_msg, force)) => {
+            return hooks::install(&file.root, commit_msg, force);
+        }
+        Scope::Hooks(HooksOp::Uninstall) => return hooks::uninstall(&file.root),
+        Scope::DevVersion(write) => return dev_version(&file, None, write),
+        Scope::GetAt(ref rev) => {
+            println!("{}", git::version_file_at(&file, rev)?.version);
+            return Ok(());
+        }
+        Scope::Package(ref name, ref pkg_scope) if let Scope::GetAt(ref rev) = **pkg_scope => {
+            let old = git::version_file_at(&file, rev)?;
+            println!("{}", old.get_package(name)?.version);
+            return Ok(());
+        }
+        Scope::Diff(ref from, ref to) => {
+            let old = git::version_file_at(&file, from)?;
+            let new = match to {
+                Some(to) => git::version_file_at(&file, to)?,
+                None => file.clone(),
+            };
+            let lines = diff::diff(&old, &new);
+            if lines.is_empty() {
+                println!("No version changes");
+            }
+            for line in lines {
+                println!("{}", line);
+            }
+            return Ok(());
+        }
+        Scope::Package(ref name, ref pkg_scope) if let Scope::DevVersion(write) = **pkg_scope => {
+            return dev_version(&file, Some(name), write);
+        }
+        Scope::Package(ref name, ref pkg_scope) if **pkg_scope == Scope::VerifyTag => {
+            return verify_tag(&file, Some(name));
+        }
+        Scope::AddChange(ref changeset) => {
+            for name in changeset.bumps.keys() {
+                if name != changes::ROOT {
+                    file.get_package(name)?;
+                }
+            }
+            let path = changes::add(&file.root, changeset)?;
+            println!(
+                "Added {}",
+                path.strip_prefix(&file.root).unwrap_or(&path).display()
+            );
+            return Ok(());
+        }
+        Scope::Changelog(ref op) => return changelog(&file, None, op),
+        Scope::Package(ref name, ref pkg_scope) if let Scope::Changelog(ref op) = **pkg_scope => {
+            return changelog(&file, Some(name), op);
+        }
+        Scope::Release => {
+            let before = file.clone();
+            let release = changes::release(&mut file)?;
+            if !release.is_empty() {
+                let bumped = hooks::bumped(&before, &file);
+                hooks::run_bump_hooks(&bumped, Stage::Pre)?;
+                file.save(file_path)?;
+                release.apply()?;
+                hooks::run_bump_hooks(&bumped, Stage::Post)?;
+            }
+            return Ok(());
+        }
+        Scope::File(Operator::Audit) => {
+            if audit::audit(&mut file, None)? {
+                file.save(file_path)?;
+            }
+            return Ok(());
+        }
+        Scope::Package(ref name, ref pkg_scope) if **pkg_scope == Scope::File(Operator::Audit) => {
+            file.get_package(name)?;
+            if audit::audit(&mut file, Some(name))? {
+                file.save(file_path)?;
+            }
+            return Ok(());
+        }
+        _ => (),
+    }
+    let before = file.clone();
     let mut ver = process_run(scope, file)?;
+    let bumped = hooks::bumped(&before, &ver);
+    hooks::run_bump_hooks(&bumped, Stage::Pre)?;
     ver.save(file_path)?;
+    hooks::run_bump_hooks(&bumped, Stage::Post)
+}
+
+/// Whether `scope` is handled by [`run`] for a single package rather than by [`process_run`],
+/// so a selector has to run it for each package in turn
+fn is_package_only(scope: &Scope) -> bool {
+    matches!(
+        scope,
+        Scope::SyncFromGit
+            | Scope::VerifyTag
+            | Scope::DevVersion(_)
+            | Scope::GetAt(_)
+            | Scope::Changelog(_)
+            | Scope::File(Operator::Audit)
+    )
+}
+
+/// Run `scope` for each package the selector picks, in dependency order, as if each had been
+/// named on the command line
+fn run_selected(
+    ver: &VersionFile,
+    selector: &Selector,
+    scope: &Scope,
+    file_path: PathBuf,
+) -> VersionResult<()> {
+    let selected = ver.select(selector)?;
+    if selected.is_empty() {
+        println!("No packages matched");
+        return Ok(());
+    }
+    for name in graph::topo_order(&ver.package)? {
+        if !selected.contains(&name) {
+            continue;
+        }
+        println!("[{}]", name);
+        let pkg_scope = Scope::Package(name.clone(), Box::new(scope.clone()));
+        match run(pkg_scope, file_path.clone()) {
+            Err(VersionError::InheritedVersion(_, inherit)) => {
+                println!("{}: skipped, inherits {}", name, inherit);
+            }
+            result => result?,
+        }
+    }
+    Ok(())
+}
+
+/// Run `scope`, then commit VERSION.toml, the tracked files and changelogs, tagging every
+/// version that changed when asked to
+fn commit(scope: Scope, file_path: PathBuf, options: &GitOptions) -> VersionResult<()> {
+    let before = VersionFile::load(file_path.clone())?;
+    if !options.allow_dirty && git::is_dirty(&before.root)? {
+        return Err(VersionError::GitError(
+            "the working tree has uncommitted changes, commit them or pass --allow-dirty"
+                .to_string(),
+        ));
+    }
+    git::check_identity(&before.root)?;
+    run(scope, file_path.clone())?;
+    let after = VersionFile::load(file_path.clone())?;
+
+    let mut released: Vec<Option<&str>> = vec![];
+    if after.version != before.version {
+        released.push(None);
+    }
+    for (name, pkg) in after.package.iter() {
+        if before
+            .package
+            .get(name)
+            .is_none_or(|old| old.version != pkg.version)
+        {
+            released.push(Some(name));
+        }
+    }
+    if released.is_empty() {
+        println!("No versions changed, nothing to commit");
+        return Ok(());
+    }
+
+    let mut paths = vec![file_path];
+    for file in after.files.iter() {
+        paths.push(file.path(&after.root)?);
+    }
+    for pkg in after.package.values() {
+        for file in pkg.files.iter() {
+            paths.push(file.path(&pkg.root)?);
+        }
+    }
+    for package in released.iter() {
+        paths.push(changelog::location(&after, *package)?.0);
+    }
+    // Consumed changesets are deleted, so they're staged if git still knows about them
+    let changes_dir = after.root.join(changes::CHANGES_DIR);
+    if changes_dir.exists()
+        || !git::git(&after.root, &["ls-files", "--", changes::CHANGES_DIR])?.is_empty()
+    {
+        paths.push(changes_dir);
+    }
+    paths.retain(|path| path.exists() || path.ends_with(changes::CHANGES_DIR));
+    paths.sort();
+    paths.dedup();
+
+    let tags: Vec<String> = released
+        .iter()
+        .map(|package| {
+            let version = match package {
+                Some(name) => &after.package[*name].version,
+                None => &after.version,
+            };
+            git::tag_name(&after, *package, version)
+        })
+        .collect();
+    // The root version when it changed, otherwise the versions of the packages that did
+    let version = match released.first() {
+        Some(None) => after.version.to_string(),
+        _ => released
+            .iter()
+            .flatten()
+            .map(|name| after.package[*name].version.to_string())
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    let message = after
+        .git
+        .message
+        .as_deref()
+        .unwrap_or("Release {tags}")
+        .replace("{tags}", &tags.join(", "))
+        .replace("{version}", &version);
+    git::commit(&after.root, &paths, &message)?;
+    println!("Committed \"{}\"", message);
+    if options.tag {
+        for tag in tags.iter() {
+            git::tag(&after.root, tag)?;
+            println!("Tagged {}", tag);
+        }
+    }
     Ok(())
 }
 
+/// Print the development version of the root or a package, and write it into its tracked
+/// files when asked to, without changing VERSION.toml
+fn dev_version(ver: &VersionFile, package: Option<&str>, write: bool) -> VersionResult<()> {
+    let version = git::dev_version(ver, package)?;
+    println!("{}", version);
+    if !write {
+        return Ok(());
+    }
+    match package {
+        Some(name) => {
+            let mut pkg = ver.get_package(name)?.clone();
+            pkg.version = version;
+            let mut packages = ver.package_versions();
+            packages.insert(name.to_string(), pkg.version.clone());
+            pkg.update_tracked_files_with(&packages)
+        }
+        None => {
+            let mut dev = ver.clone();
+            dev.version = version;
+            dev.sync_files()
+        }
+    }
+}
+
+/// The operator that sets the root or a package to the version of its highest release tag
+fn sync_from_git(ver: &VersionFile, package: Option<&str>) -> VersionResult<Operator> {
+    if let Some(name) = package
+        && let Some(ref inherit) = ver.get_package(name)?.inherit
+    {
+        return Err(VersionError::InheritedVersion(
+            name.to_string(),
+            inherit.clone(),
+        ));
+    }
+    match git::latest_release(ver, package)? {
+        Some((tag, version)) => {
+            println!("Found {}", tag);
+            Ok(Operator::SetVersion(version.to_string()))
+        }
+        None => Err(VersionError::GitError(format!(
+            "no tags match {}",
+            git::tag_pattern(ver, package)
+        ))),
+    }
+}
+
+/// Check that every release tag on HEAD, for the root and every package or for one package,
+/// is for the version in VERSION.toml
+fn verify_tag(ver: &VersionFile, package: Option<&str>) -> VersionResult<()> {
+    let targets: Vec<Option<&str>> = match package {
+        Some(name) => {
+            ver.get_package(name)?;
+            vec![Some(name)]
+        }
+        None => std::iter::once(None)
+            .chain(ver.package.keys().map(|name| Some(name.as_str())))
+            .collect(),
+    };
+    let mut verified = 0;
+    for tag in git::tags_at(&ver.root, "HEAD")? {
+        for target in targets.iter() {
+            let version = match git::tag_version(ver, *target, &tag) {
+                Some(version) => version,
+                None => continue,
+            };
+            let expected = match target {
+                Some(name) => &ver.get_package(name)?.version,
+                None => &ver.version,
+            };
+            if version != *expected {
+                return Err(VersionError::TagMismatch(tag, expected.clone()));
+            }
+            println!("{} matches VERSION.toml", tag);
+            verified += 1;
+        }
+    }
+    if verified == 0 {
+        return Err(VersionError::GitError(
+            "no release tag points at HEAD".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+pub type ScopeResult<'a, T> = VersionResult<(&'a mut Version, &'a mut T, Option<(String, Scope)>)>;
+
 pub fn run_scopes<'a, T>(
     scope: Scope,
     version: &'a mut Version,
     files: &'a mut T,
-) -> VersionResult<(&'a mut Version, &'a mut T, Option<(String, Scope)>)>
+) -> ScopeResult<'a, T>
 where
     T: ModifyTrackedFiles,
 {
@@ -236,24 +586,36 @@ where
             _ => return Err(VersionError::InvalidOperation),
         },
         Scope::Build(getsetbuild) => match getsetbuild {
-            Operator::Set(set) => match set {
-                SetTypes::String(value) => version.build = BuildMetadata::new(&value)?,
-                _ => return Err(VersionError::InvalidOperation),
-            },
+            Operator::Set(SetTypes::String(value)) => version.build = BuildMetadata::new(&value)?,
             Operator::Get => println!("{}", version.build),
             Operator::Rm => version.build = BuildMetadata::EMPTY,
             _ => return Err(VersionError::InvalidOperation),
         },
-        Scope::Get => println!("{}", version.to_string()),
+        Scope::Get => println!("{}", version),
         Scope::Version => println!("{}.{}.{}", version.major, version.minor, version.patch),
         Scope::Revision => println!("{}", version.pre),
+        // Paths come from the command line, relative to the working directory
         Scope::File(file_cmd) => match file_cmd {
-            Operator::AddFile(set) => match set {
-                SetTypes::NewFile(file) => files.add_tracked_file(file)?,
-                _ => return Err(VersionError::InvalidOperation),
-            },
-            Operator::Update(file) => files.update_file(PathBuf::from(file))?,
-            Operator::RmFile(file) => files.remove_tracked_file(PathBuf::from(file))?,
+            Operator::AddFile(SetTypes::NewFile(mut file), force) => {
+                file.file = root_relative(&file.file, files.root())?;
+                track_file(files, file, force, version)?
+            }
+            Operator::AddFile(SetTypes::SuggestFile(mut file, line), force) => {
+                file.file = root_relative(&file.file, files.root())?;
+                let suggestions = file.suggest(&version.to_string(), files.root())?;
+                file.expr = choose_suggestion(&file.file, suggestions, line)?;
+                println!("expression: {}", file.expr);
+                track_file(files, file, force, version)?
+            }
+            Operator::Update(file) => {
+                files.update_file(PathBuf::from(root_relative(&file, files.root())?))?
+            }
+            Operator::Import(file) => {
+                *version = files.import_file(PathBuf::from(root_relative(&file, files.root())?))?
+            }
+            Operator::RmFile(file) => {
+                files.remove_tracked_file(PathBuf::from(root_relative(&file, files.root())?))?
+            }
             Operator::UpdateAll => files.update_tracked_files()?,
             Operator::ListFiles => {
                 let files = files.list_tracked_files()?;
@@ -262,12 +624,9 @@ where
             }
             _ => return Err(VersionError::InvalidOperation),
         },
-        Scope::Set(set) => match set {
-            Operator::SetVersion(version_str) => {
-                *version = Version::parse(&version_str)?;
-            }
-            _ => return Err(VersionError::InvalidOperation),
-        },
+        Scope::Set(Operator::SetVersion(version_str)) => {
+            *version = Version::parse(&version_str)?;
+        }
         Scope::Package(name, pkg) => return Ok((version, files, Some((name, *pkg)))),
         _ => return Err(VersionError::InvalidOperation),
     };
@@ -275,34 +634,371 @@ where
     Ok((version, files, None))
 }
 
+fn track_file<T>(
+    files: &mut T,
+    file: TrackedFiles,
+    force: bool,
+    version: &Version,
+) -> VersionResult<()>
+where
+    T: ModifyTrackedFiles,
+{
+    let name = file.file.clone();
+    let markers = file.markers;
+    match files.track_file(file, force)? {
+        Some(found) if markers => println!("{}:{}: {}", name, found.line, found.text.trim()),
+        Some(found) => {
+            println!("{}:{}: {}", name, found.line, found.text.trim());
+            println!("will replace '{}' with '{}'", found.value, version);
+        }
+        None if markers => println!("warning: no markers found in {}", name),
+        None => println!("warning: expression did not match anything in {}", name),
+    }
+    Ok(())
+}
+
+/// Pick the suggested expression for the requested line, asking the user when it's ambiguous
+fn choose_suggestion(
+    file: &str,
+    suggestions: Vec<Suggestion>,
+    line: Option<usize>,
+) -> VersionResult<String> {
+    if let Some(line) = line {
+        return match suggestions.into_iter().find(|s| s.found.line == line) {
+            Some(suggestion) => Ok(suggestion.expr),
+            None => Err(VersionError::NoMatch(format!("{}:{}", file, line))),
+        };
+    }
+    match suggestions.len() {
+        0 => Err(VersionError::NoMatch(file.to_string())),
+        1 => Ok(suggestions[0].expr.clone()),
+        count => {
+            for (idx, suggestion) in suggestions.iter().enumerate() {
+                println!(
+                    "{}) {}:{}: {}",
+                    idx + 1,
+                    file,
+                    suggestion.found.line,
+                    suggestion.found.text.trim()
+                );
+            }
+            match prompt::choose("Which line should be tracked?", count)? {
+                Some(choice) => Ok(suggestions[choice].expr.clone()),
+                None => Err(VersionError::AmbiguousMatch(file.to_string())),
+            }
+        }
+    }
+}
+
 pub fn process_run(scope: Scope, mut ver: VersionFile) -> VersionResult<VersionFile> {
+    if let Scope::Packages(selector, scope) = scope {
+        run_packages(&mut ver, &selector, *scope)?;
+        return Ok(ver);
+    }
     let mut ver_files = ver.clone();
     let mut ver_version = ver.version.clone();
+    let old_root = ver.version.clone();
     let (version, files, scope) = run_scopes(scope, &mut ver_version, &mut ver_files)?;
     ver = files.clone();
     ver.version = version.clone();
+    if scope.is_none() && old_root != ver.version {
+        let changes = graph::propagate_root(&mut ver, &old_root)?;
+        if !changes.is_empty() {
+            println!("root: {} -> {}", old_root, ver.version);
+            for change in changes.iter() {
+                println!("{}", change.describe());
+            }
+        }
+    }
     if let Some((name, scope)) = scope {
-        if scope == Scope::ListPackages {
-            for (name, pkg) in ver.package.iter() {
-                println!("{}: {}", name, pkg.version.to_string());
+        match scope {
+            Scope::ListPackages => {
+                for (name, pkg) in ver.package.iter() {
+                    println!("{}: {}", name, pkg.version);
+                }
+                return Ok(ver);
+            }
+            Scope::ChangedPackages(ref since) => {
+                for changed in git::changed_packages(&ver, since.as_deref())? {
+                    println!("{}", changed.describe());
+                }
+                return Ok(ver);
+            }
+            Scope::BumpChanged(level, ref since) => {
+                bump_changed(&mut ver, level, since.as_deref())?;
+                return Ok(ver);
+            }
+            Scope::AddPackage(ref version, ref path) => {
+                let pkg = Package {
+                    version: Version::parse(version)?,
+                    path: path.clone(),
+                    ..Package::default()
+                };
+                ver.add_package(&name, pkg)?;
+                return Ok(ver);
+            }
+            Scope::RenamePackage(ref to) => {
+                ver.rename_package(&name, to)?;
+                return Ok(ver);
+            }
+            Scope::CopyPackage(ref to) => {
+                ver.copy_package(&name, to)?;
+                return Ok(ver);
             }
-            return Ok(ver);
+            _ => (),
         }
-        if !ver.package.contains_key(&name) {
-            ver.package.insert(name.clone(), Package::default());
+        let (old_version, changes) = run_package(&mut ver, &name, scope)?;
+        if !changes.is_empty() {
+            let version = &ver.get_package(&name)?.version;
+            println!("{}: {} -> {}", name, old_version, version);
+            for change in changes.iter() {
+                println!("{}", change.describe());
+            }
         }
-        let pkg = ver.get_package_mut(&name)?;
-        let mut pkg_version = pkg.version.clone();
-        let mut pkg_files = pkg.clone();
-        let (version, files, _) = match scope {
-            Scope::RmPackage => {
-                ver.package.remove(&name);
-                return Ok(ver);
+    }
+    Ok(ver)
+}
+
+/// Run `scope` against one package, returning the version it had before and the changes
+/// carried over to other packages
+fn run_package(
+    ver: &mut VersionFile,
+    name: &str,
+    scope: Scope,
+) -> VersionResult<(Version, Vec<Change>)> {
+    let pkg = ver.get_package_mut(name)?;
+    let old_version = pkg.version.clone();
+    let mut pkg_version = pkg.version.clone();
+    let mut pkg_files = pkg.clone();
+    let (version, files, _) = match scope {
+        Scope::RmPackage => {
+            ver.package.remove(name);
+            return Ok((old_version, vec![]));
+        }
+        _ => run_scopes(scope, &mut pkg_version, &mut pkg_files)?,
+    };
+    if old_version != *version
+        && let Some(ref inherit) = files.inherit
+    {
+        return Err(VersionError::InheritedVersion(
+            name.to_string(),
+            inherit.clone(),
+        ));
+    }
+    files.version = version.clone();
+    ver.package.insert(name.to_string(), files.clone());
+    let changes = graph::propagate(ver, name, &old_version)?;
+    Ok((old_version, changes))
+}
+
+/// Run `scope` against every selected package, printing what happened to each
+///
+/// Packages run in dependency order. Those already changed by an earlier package's group,
+/// dependents, or inheritance are skipped, so nothing is bumped twice, and so are packages
+/// that inherit their version.
+fn run_packages(ver: &mut VersionFile, selector: &Selector, scope: Scope) -> VersionResult<()> {
+    if scope.is_multi_package()
+        || matches!(
+            scope,
+            Scope::AddPackage(_, _) | Scope::RenamePackage(_) | Scope::CopyPackage(_)
+        )
+    {
+        return Err(VersionError::InvalidOperation);
+    }
+    let selected = ver.select(selector)?;
+    if selected.is_empty() {
+        println!("No packages matched");
+        return Ok(());
+    }
+    let names: Vec<String> = graph::topo_order(&ver.package)?
+        .into_iter()
+        .filter(|name| selected.contains(name))
+        .collect();
+    if scope == Scope::Get {
+        for name in names.iter() {
+            println!("{}: {}", name, ver.get_package(name)?.version);
+        }
+        return Ok(());
+    }
+    let mut changed: Vec<String> = vec![];
+    for name in names.iter() {
+        if changed.contains(name) {
+            continue;
+        }
+        let (old_version, changes) = match run_package(ver, name, scope.clone()) {
+            Err(VersionError::InheritedVersion(_, inherit)) => {
+                println!("{}: skipped, inherits {}", name, inherit);
+                continue;
             }
-            _ => run_scopes(scope, &mut pkg_version, &mut pkg_files)?,
+            result => result?,
         };
-        files.version = version.clone();
-        ver.package.insert(name.clone(), files.clone());
+        match ver.package.get(name) {
+            None => println!("{}: removed", name),
+            Some(pkg) if pkg.version == old_version => {
+                println!("{}: {} (unchanged)", name, old_version)
+            }
+            Some(pkg) => println!("{}: {} -> {}", name, old_version, pkg.version),
+        }
+        for change in changes {
+            println!("{}", change.describe());
+            changed.push(change.package);
+        }
     }
-    Ok(ver)
+    Ok(())
+}
+
+/// Add a changelog entry, release the Unreleased section, or show a release for the root or a
+/// package
+fn changelog(ver: &VersionFile, package: Option<&str>, op: &ChangelogOp) -> VersionResult<()> {
+    let (path, label) = changelog::location(ver, package)?;
+    match op {
+        ChangelogOp::Add(kind, message) => changelog::add_entry(&path, *kind, message),
+        ChangelogOp::Release => {
+            let version = match package {
+                Some(name) => &ver.get_package(name)?.version,
+                None => &ver.version,
+            };
+            let tag = git::tag_name(ver, package, version);
+            changelog::release(&path, &label, &tag, ver.changelog.repository.as_deref())?;
+            let shown = path.strip_prefix(&ver.root).unwrap_or(&path);
+            println!("Released {} in {}", label, shown.display());
+            Ok(())
+        }
+        ChangelogOp::Show(version, as_json) => {
+            let (path, label) = match version {
+                Some(version) => changelog::location_of(ver, package, version)?,
+                None => (path, label),
+            };
+            let notes = changelog::show(&path, &label)?;
+            if *as_json {
+                println!("{}", json!(notes));
+            } else {
+                println!("{}", notes.markdown);
+            }
+            Ok(())
+        }
+        ChangelogOp::Generate(from, to, plain) => {
+            let pathspec = match package {
+                Some(name) => ver.get_package(name)?.path.as_deref(),
+                None => None,
+            };
+            // A tagged release starts after the tag before it, anything else after the latest
+            let (path, label, before) = match released(ver, package, to)? {
+                Some((path, label)) => (path, Some(label), format!("{}^", to)),
+                None => (path, None, to.clone()),
+            };
+            let from = match from {
+                Some(from) => Some(from.clone()),
+                None => git::last_tag(ver, package, &before)?,
+            };
+            let commits = git::commits(&ver.root, from.as_deref(), to, pathspec)?;
+            let sections = changelog::commit_sections(&commits, &ver.changelog, *plain);
+            let date = changelog::date(git::commit_time(&ver.root, to)?);
+            let added = changelog::merge_release(&path, label.as_deref(), &date, &sections)?;
+            let shown = path.strip_prefix(&ver.root).unwrap_or(&path);
+            println!(
+                "Added {} {} to {} in {}",
+                added,
+                if added == 1 { "entry" } else { "entries" },
+                label.as_deref().unwrap_or("Unreleased"),
+                shown.display()
+            );
+            Ok(())
+        }
+    }
+}
+
+/// The changelog and label for the release tagged `rev`, `None` when it isn't a release tag
+fn released(
+    ver: &VersionFile,
+    package: Option<&str>,
+    rev: &str,
+) -> VersionResult<Option<(PathBuf, String)>> {
+    match git::tag_version(ver, package, rev) {
+        Some(version) => changelog::location_of(ver, package, &version).map(Some),
+        None => Ok(None),
+    }
+}
+
+/// Bump every package changed since its last release, skipping those that inherit a version
+fn bump_changed(ver: &mut VersionFile, level: Bump, since: Option<&str>) -> VersionResult<()> {
+    let mut bumped: Vec<String> = vec![];
+    for changed in git::changed_packages(ver, since)? {
+        // An earlier bump may already have cascaded into this package
+        let pkg = ver.get_package_mut(&changed.name)?;
+        if pkg.inherit.is_some() || bumped.contains(&changed.name) {
+            continue;
+        }
+        let from = pkg.version.clone();
+        bump(&mut pkg.version, level);
+        println!("{}: {} -> {}", changed.name, from, pkg.version);
+        bumped.push(changed.name.clone());
+        for change in graph::propagate(ver, &changed.name, &from)? {
+            println!("{}", change.describe());
+            bumped.push(change.package.clone());
+        }
+    }
+    if bumped.is_empty() {
+        println!("No packages changed");
+    }
+    Ok(())
+}
+
+/// Report whether every tracked file contains the version it should, without writing anything
+pub fn check(ver: &VersionFile) -> VersionResult<()> {
+    let packages = ver.package_versions();
+    let mut failures = check_files("root", &ver.files, &ver.version, &packages, &ver.root);
+    for (name, pkg) in ver.package.iter() {
+        failures += check_files(name, &pkg.files, &pkg.version, &packages, &pkg.root);
+    }
+    graph::validate_groups(ver)?;
+    for (group, members) in ver.groups.iter() {
+        let versions: Vec<String> = members
+            .iter()
+            .map(|m| format!("{} {}", m, packages[m]))
+            .collect();
+        if members.iter().any(|m| packages[m] != packages[&members[0]]) {
+            failures += 1;
+            println!("[{}] drift group: {}", group, versions.join(", "));
+        }
+    }
+    if failures > 0 {
+        return Err(VersionError::Drift(failures));
+    }
+    Ok(())
+}
+
+fn check_files(
+    scope: &str,
+    files: &[TrackedFiles],
+    version: &Version,
+    packages: &PackageVersions,
+    root: &Path,
+) -> usize {
+    let mut failures = 0;
+    for file in files.iter() {
+        match file.check(version, packages, root) {
+            Ok(FileStatus::Synced) => println!("[{}] ok {}: {}", scope, file.file, version),
+            Ok(FileStatus::Drifted(found, expected)) => {
+                failures += 1;
+                println!(
+                    "[{}] drift {}:{}: found {}, expected {}",
+                    scope, file.file, found.line, found.value, expected
+                );
+            }
+            Ok(FileStatus::NoMatch) if file.markers => {
+                failures += 1;
+                println!("[{}] no match {}: no markers found", scope, file.file);
+            }
+            Ok(FileStatus::NoMatch) => {
+                failures += 1;
+                println!("[{}] no match {}: '{}'", scope, file.file, file.expr);
+            }
+            Err(e) => {
+                failures += 1;
+                println!("[{}] error {}: {}", scope, file.file, e);
+            }
+        }
+    }
+    failures
 }
@@ -1,6 +1,12 @@
 use crate::VersionError;
+use crate::changelog::EntryType;
+use crate::changes::Changeset;
 use crate::files::TrackedFiles;
-use semver::Prerelease;
+use crate::git::GitOptions;
+use clap::ValueEnum;
+use semver::{BuildMetadata, Prerelease, Version};
+use serde::{Deserialize, Serialize};
+use std::{fmt, path::PathBuf};
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operator {
@@ -8,11 +14,13 @@ pub enum Operator {
     Rm,
     Reset,
     Get,
-    AddFile(SetTypes),
+    AddFile(SetTypes, bool),
     SetVersion(String),
     Update(String),
+    Import(String),
     RmFile(String),
     UpdateAll,
+    Audit,
     ListFiles,
     None,
 }
@@ -24,6 +32,7 @@ pub enum SetTypes {
     Number(u64),
     String(String),
     NewFile(TrackedFiles),
+    SuggestFile(TrackedFiles, Option<usize>),
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -42,8 +51,99 @@ pub enum Scope {
     Revision,
     File(Operator),
     Package(String, Box<Scope>),
+    /// Run the scope against every package the selector picks
+    Packages(Selector, Box<Scope>),
     ListPackages,
     RmPackage,
+    /// Create the package with a version and an optional path
+    AddPackage(String, Option<String>),
+    RenamePackage(String),
+    CopyPackage(String),
+    /// List the packages changed since their last release, or since a ref
+    ChangedPackages(Option<String>),
+    BumpChanged(Bump, Option<String>),
+    Check,
+    AddChange(Changeset),
+    Release,
+    Changelog(ChangelogOp),
+    /// Run the scope, then commit and tag whatever it changed
+    Commit(GitOptions, Box<Scope>),
+    /// Set the version from the highest release tag
+    SyncFromGit,
+    /// Print a development version from git, optionally writing it into the tracked files
+    DevVersion(bool),
+    /// Print the version as of a git revision
+    GetAt(String),
+    /// Compare two git revisions, or one against the working tree
+    Diff(String, Option<String>),
+    /// Merge the base, our and their VERSION.toml files, writing the result over ours
+    MergeDriver(PathBuf, PathBuf, PathBuf),
+    VerifyTag,
+    Hooks(HooksOp),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChangelogOp {
+    Add(EntryType, String),
+    Release,
+    /// Print the notes for a release, the current version's when none is given, optionally as
+    /// JSON
+    Show(Option<Version>, bool),
+    /// Merge entries for the commits between two revisions, optionally without grouping them
+    /// by Conventional Commit type
+    Generate(Option<String>, String, bool),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum HooksOp {
+    /// Install the pre-commit hook, the commit-msg hook too when set, optionally replacing
+    /// hooks from elsewhere without asking
+    Install(bool, bool),
+    Uninstall,
+    /// Check the commit message in a file
+    CheckMessage(PathBuf),
+}
+
+/// Picks several packages at once
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selector {
+    All,
+    /// Packages whose names match a glob, where `*` matches any run of characters and `?` any
+    /// single one
+    Match(String),
+    Group(String),
+}
+
+impl Scope {
+    /// Whether a package scope works across every package rather than a single named one
+    pub fn is_multi_package(&self) -> bool {
+        matches!(
+            self,
+            Scope::ListPackages | Scope::ChangedPackages(_) | Scope::BumpChanged(_, _)
+        )
+    }
+
+    /// Whether the scope can change a version, which is all `--commit` knows how to commit
+    pub fn changes_version(&self) -> bool {
+        match self {
+            Scope::Major(op)
+            | Scope::Minor(op)
+            | Scope::Patch(op)
+            | Scope::Alpha(op)
+            | Scope::Beta(op)
+            | Scope::Pre(op)
+            | Scope::RC(op)
+            | Scope::Build(op)
+            | Scope::Set(op) => matches!(
+                op,
+                Operator::Set(_) | Operator::Rm | Operator::Reset | Operator::SetVersion(_)
+            ),
+            Scope::File(op) => matches!(op, Operator::Import(_)),
+            Scope::Package(_, scope) | Scope::Packages(_, scope) => scope.changes_version(),
+            Scope::BumpChanged(_, _) | Scope::Release | Scope::SyncFromGit => true,
+            _ => false,
+        }
+    }
 }
 
 pub struct PrereleaseWrapper {
@@ -82,8 +182,46 @@ impl TryFrom<Prerelease> for PrereleaseWrapper {
     }
 }
 
-impl ToString for PrereleaseWrapper {
-    fn to_string(&self) -> String {
-        format!("{}.{}", self.pre, self.num)
+impl fmt::Display for PrereleaseWrapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.pre, self.num)
+    }
+}
+
+/// A SemVer bump level
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Bump {
+    Patch,
+    Minor,
+    Major,
+}
+
+impl fmt::Display for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Bump::Patch => write!(f, "patch"),
+            Bump::Minor => write!(f, "minor"),
+            Bump::Major => write!(f, "major"),
+        }
+    }
+}
+
+/// Increment `version` at `level`, resetting the lower components and dropping any
+/// prerelease or build identifiers
+pub fn bump(version: &mut Version, level: Bump) {
+    match level {
+        Bump::Major => {
+            version.major += 1;
+            version.minor = 0;
+            version.patch = 0;
+        }
+        Bump::Minor => {
+            version.minor += 1;
+            version.patch = 0;
+        }
+        Bump::Patch => version.patch += 1,
     }
+    version.pre = Prerelease::EMPTY;
+    version.build = BuildMetadata::EMPTY;
 }
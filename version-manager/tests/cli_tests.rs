@@ -1,4 +1,12 @@
-use std::time::Duration;
+use std::{env, path::PathBuf, time::Duration};
+
+/// Find an executable on the PATH
+fn which(name: &str) -> PathBuf {
+    env::split_paths(&env::var_os("PATH").unwrap_or_default())
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+        .unwrap_or_else(|| panic!("{} not found on PATH", name))
+}
 
 #[test]
 fn cli_tests() {
@@ -8,7 +16,45 @@ fn cli_tests() {
         .case("tests/cmd/minor.trycmd")
         .case("tests/cmd/patch.trycmd")
         .case("tests/cmd/rc.trycmd")
+        .case("tests/cmd/package.trycmd")
+        .case("tests/cmd/check.trycmd")
+        .case("tests/cmd/import.trycmd")
+        .case("tests/cmd/track.trycmd")
+        .case("tests/cmd/track_cwd.toml")
+        .case("tests/cmd/track_package.toml")
+        .case("tests/cmd/suggest.trycmd")
+        .case("tests/cmd/audit.trycmd")
+        .case("tests/cmd/markers.trycmd")
+        .case("tests/cmd/deps.trycmd")
+        .case("tests/cmd/groups.trycmd")
+        .case("tests/cmd/inherit.trycmd")
+        .case("tests/cmd/lifecycle.trycmd")
+        .case("tests/cmd/monorepo.toml")
+        .case("tests/cmd/changed.trycmd")
+        .case("tests/cmd/select.trycmd")
+        .case("tests/cmd/release.trycmd")
+        .case("tests/cmd/changelog.trycmd")
+        .case("tests/cmd/changelog_show.trycmd")
+        .case("tests/cmd/changelog_prefix.trycmd")
+        .case("tests/cmd/generate.trycmd")
+        .case("tests/cmd/commit.trycmd")
+        .case("tests/cmd/commit_message.trycmd")
+        .case("tests/cmd/sync.trycmd")
+        .case("tests/cmd/dev.trycmd")
+        .case("tests/cmd/history.trycmd")
+        .case("tests/cmd/merge.trycmd")
+        .case("tests/cmd/hooks.trycmd")
+        .case("tests/cmd/bump_hooks.trycmd")
+        .register_bin("git", which("git"))
         .timeout(Duration::new(1, 0))
         .env("RUST_BACKTRACE", "1")
+        .env("SOURCE_DATE_EPOCH", "1712620800")
+        .env("GIT_AUTHOR_DATE", "2024-04-09T00:00:00Z")
+        .env("GIT_COMMITTER_DATE", "2024-04-09T00:00:00Z")
+        .env("GIT_AUTHOR_NAME", "Test")
+        .env("GIT_AUTHOR_EMAIL", "test@example.com")
+        .env("GIT_COMMITTER_NAME", "Test")
+        .env("GIT_COMMITTER_EMAIL", "test@example.com")
+        .env("GIT_CONFIG_GLOBAL", "/dev/null")
         .run();
 }
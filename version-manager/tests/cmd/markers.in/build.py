@@ -0,0 +1 @@
+MAJOR = 1  # version-manager: major
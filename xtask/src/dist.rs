@@ -13,9 +13,9 @@ pub fn dist_dir(target: Option<&str>) -> PathBuf {
 }
 pub fn dist() -> Result<()> {
     if dist_dir(None).exists() {
-        let _ = fs::remove_dir_all(&dist_dir(None));
+        let _ = fs::remove_dir_all(dist_dir(None));
     }
-    fs::create_dir_all(&dist_dir(None))?;
+    fs::create_dir_all(dist_dir(None))?;
 
     dist_binary(None, None)?;
     dist_manpage(None)?;
@@ -30,7 +30,7 @@ pub fn dist_binary_cargo(target: Targets) -> Result<()> {
     let cargo = env::var("CARGO").unwrap_or_else(|_| "cargo".to_string());
     let status = Command::new(cargo)
         .current_dir(project_root())
-        .args(&[
+        .args([
             "build",
             "--package",
             CRATE_NAME,
@@ -49,7 +49,7 @@ pub fn dist_binary_cargo(target: Targets) -> Result<()> {
 pub fn dist_binary_cross(target: Targets) -> Result<()> {
     let status = Command::new("cross")
         .current_dir(project_root())
-        .args(&[
+        .args([
             "build",
             "--package",
             CRATE_NAME,
@@ -70,9 +70,9 @@ pub fn dist_binary(target: Option<Targets>, output: Option<&str>) -> Result<()>
     let os = TryInto::<Targets>::try_into(info.clone())?;
     match target {
         Some(tar) => {
-            if os == tar {
-                dist_binary_cargo(tar)?;
-            } else if (info.os_type() == Type::Macos) && (Into::<Type>::into(tar) == Type::Macos) {
+            if os == tar
+                || (info.os_type() == Type::Macos && Into::<Type>::into(tar) == Type::Macos)
+            {
                 dist_binary_cargo(tar)?;
             } else {
                 dist_binary_cross(tar)?;
@@ -111,9 +111,9 @@ pub fn dist_readme(target: Option<&str>) -> Result<()> {
     drop(readme);
 
     if dist_dir(target).join("doc").exists() {
-        let _ = fs::remove_dir_all(&dist_dir(target).join("doc"));
+        let _ = fs::remove_dir_all(dist_dir(target).join("doc"));
     }
-    fs::create_dir_all(&dist_dir(target).join("doc"))?;
+    fs::create_dir_all(dist_dir(target).join("doc"))?;
 
     fs::copy(
         project_root().join("README.md"),
@@ -15,8 +15,8 @@ use error::Result;
 
 use self::targets::Arch;
 
-const PACKAGE_NAME: &'static str = "version";
-const CRATE_NAME: &'static str = "version-manager";
+const PACKAGE_NAME: &str = "version";
+const CRATE_NAME: &str = "version-manager";
 
 fn main() {
     if let Err(e) = try_main() {
@@ -84,11 +84,11 @@ fn target(target: Targets, up: bool) -> Result<()> {
             .join(PACKAGE_NAME)
             .is_file()
         {
-            let _ = fs::remove_file(&dist::dist_dir(Some(target.into())).join(PACKAGE_NAME));
+            let _ = fs::remove_file(dist::dist_dir(Some(target.into())).join(PACKAGE_NAME));
         }
-        let _ = fs::remove_dir_all(&dist::dist_dir(Some(target.into())).join(PACKAGE_NAME));
+        let _ = fs::remove_dir_all(dist::dist_dir(Some(target.into())).join(PACKAGE_NAME));
     }
-    fs::create_dir_all(&dist::dist_dir(Some(target.into())).join(PACKAGE_NAME))?;
+    fs::create_dir_all(dist::dist_dir(Some(target.into())).join(PACKAGE_NAME))?;
     let target_dir = format!("{}/{}", target, PACKAGE_NAME);
 
     install_target(target)?;
@@ -107,7 +107,7 @@ fn install_targets() -> Result<()> {
     for target in Targets::iter() {
         let status = Command::new(&rustup)
             .current_dir(project_root())
-            .args(&["target", "add", target.into()])
+            .args(["target", "add", target.into()])
             .status()?;
 
         if !status.success() {
@@ -122,7 +122,7 @@ fn install_target(target: Targets) -> Result<()> {
     let rustup = env::var("RUSTUP").unwrap_or_else(|_| "rustup".to_string());
     let status = Command::new(&rustup)
         .current_dir(project_root())
-        .args(&["target", "add", target.into()])
+        .args(["target", "add", target.into()])
         .status()?;
 
     if !status.success() {
@@ -136,23 +136,23 @@ fn package_targets(up: bool) -> Result<()> {
     for target in Targets::iter() {
         if dist::dist_dir(Some(&format!("{}/{}", target, PACKAGE_NAME))).exists() {
             if dist::dist_dir(Some(&format!("{}/{}", target, PACKAGE_NAME))).is_file() {
-                let _ = fs::remove_file(&dist::dist_dir(Some(&format!(
+                let _ = fs::remove_file(dist::dist_dir(Some(&format!(
                     "{}/{}",
                     target, PACKAGE_NAME
                 ))));
             }
-            let _ = fs::remove_dir_all(&dist::dist_dir(Some(&format!(
+            let _ = fs::remove_dir_all(dist::dist_dir(Some(&format!(
                 "{}/{}",
                 target, PACKAGE_NAME
             ))));
         }
-        fs::create_dir_all(&dist::dist_dir(Some(&format!(
+        fs::create_dir_all(dist::dist_dir(Some(&format!(
             "{}/{}",
             target, PACKAGE_NAME
         ))))?;
 
         install_target(target)?;
-        dist::dist_binary(Some(target.into()), Some(PACKAGE_NAME))?;
+        dist::dist_binary(Some(target), Some(PACKAGE_NAME))?;
         dist::dist_manpage(Some(&format!("{}/{}", target, PACKAGE_NAME)))?;
         dist::dist_readme(Some(&format!("{}/{}", target, PACKAGE_NAME)))?;
         dist::dist_license(Some(&format!("{}/{}", target, PACKAGE_NAME)))?;
@@ -165,15 +165,15 @@ fn package_targets(up: bool) -> Result<()> {
 
 fn build_targets() -> Result<()> {
     if dist::dist_dir(None).exists() {
-        let _ = fs::remove_dir_all(&dist::dist_dir(None));
+        let _ = fs::remove_dir_all(dist::dist_dir(None));
     }
-    fs::create_dir_all(&dist::dist_dir(None))?;
+    fs::create_dir_all(dist::dist_dir(None))?;
 
     for target in Targets::iter() {
         if dist::dist_dir(Some(target.into())).exists() {
-            let _ = fs::remove_dir_all(&dist::dist_dir(Some(target.into())));
+            let _ = fs::remove_dir_all(dist::dist_dir(Some(target.into())));
         }
-        fs::create_dir_all(&dist::dist_dir(Some(target.into())))?;
+        fs::create_dir_all(dist::dist_dir(Some(target.into())))?;
 
         dist::dist_binary(Some(target), None)?;
     }
@@ -184,11 +184,11 @@ fn build_targets() -> Result<()> {
 fn package(up: bool) -> Result<()> {
     if dist::dist_dir(Some(PACKAGE_NAME)).exists() {
         if dist::dist_dir(Some(PACKAGE_NAME)).is_file() {
-            let _ = fs::remove_file(&dist::dist_dir(Some(PACKAGE_NAME)));
+            let _ = fs::remove_file(dist::dist_dir(Some(PACKAGE_NAME)));
         }
-        let _ = fs::remove_dir_all(&dist::dist_dir(Some(PACKAGE_NAME)));
+        let _ = fs::remove_dir_all(dist::dist_dir(Some(PACKAGE_NAME)));
     }
-    fs::create_dir_all(&dist::dist_dir(Some(PACKAGE_NAME)))?;
+    fs::create_dir_all(dist::dist_dir(Some(PACKAGE_NAME)))?;
 
     dist::dist_binary(None, Some(PACKAGE_NAME))?;
     dist::dist_manpage(Some(PACKAGE_NAME))?;
@@ -230,7 +230,7 @@ fn generate_rpm(target: Option<Targets>) -> Result<()> {
                 dist::dist_dir(Some(&tgt.to_string())).to_string_lossy(),
                 PACKAGE_NAME,
                 env!("CARGO_PKG_VERSION"),
-                &tgt
+                tgt
             ))
             .stdout(Stdio::null())
             .status()?,
@@ -335,6 +335,7 @@ fn package_release(target: Option<Targets>, up: bool) -> Result<()> {
     generate_deb(target)?;
 
     if up {
+        release_notes(version)?;
         match target {
             Some(tgt) => publish(
                 version.to_string(),
@@ -375,6 +376,43 @@ fn package_release(target: Option<Targets>, up: bool) -> Result<()> {
     Ok(())
 }
 
+fn release_notes(version: &str) -> Result<()> {
+    let changelog = fs::read_to_string(project_root().join("CHANGELOG.md"))?;
+    let notes =
+        version_manager::changelog::find_release(&changelog, version).map_err(|e| e.to_string())?;
+    let notes = match notes {
+        Some(notes) => notes,
+        None => {
+            eprintln!(
+                "No CHANGELOG.md section for {}, skipping release notes",
+                version
+            );
+            return Ok(());
+        }
+    };
+    let notes_file = dist::dist_dir(None).join("RELEASE_NOTES.md");
+    fs::write(&notes_file, notes.markdown)?;
+
+    let status = Command::new("gh")
+        .arg("release")
+        .arg("edit")
+        .arg(format!("v{}", version))
+        .arg("--notes-file")
+        .arg(&notes_file)
+        .stdout(Stdio::null())
+        .status()?;
+
+    if !status.success() {
+        return Err(format!(
+            "Command 'gh release edit v{} --notes-file {}' was unsuccessful",
+            version,
+            notes_file.display()
+        )
+        .into());
+    }
+    Ok(())
+}
+
 fn publish(version: String, file: String) -> Result<()> {
     let status = Command::new("gh")
         .arg("release")